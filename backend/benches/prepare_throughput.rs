@@ -0,0 +1,225 @@
+// Throughput of preparing create_todo transactions against a local mock RPC node.
+//
+// Compares the old approach (a fresh blocking RpcClient per request, run on the
+// blocking thread pool) with TodoService over SolanaService's shared nonblocking
+// client.
+//
+// Run with: cargo bench --bench prepare_throughput
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction, message::Message,
+    pubkey::Pubkey, system_program, transaction::Transaction,
+};
+use solana_todo::{pda, UserProfile};
+use solana_todo_backend::{
+    config::{ChainBackend, SolanaConfig},
+    models::todo::CreateTodoRequest,
+    services::{ChainClient, SolanaService, TodoService},
+};
+
+const PROGRAM_ID: &str = "Ct2N3zw5LFiNj5mJ7hN2c4umze2pAWNjfYqazZHzDENy";
+const REQUESTS: usize = 2_000;
+const CONCURRENCY: usize = 64;
+// Simulated round trip to an RPC node
+const RPC_LATENCY: Duration = Duration::from_millis(2);
+
+// Minimal JSON-RPC node answering the calls the prepare path makes
+async fn rpc_handler(body: web::Json<Value>, profile: web::Data<String>) -> HttpResponse {
+    tokio::time::sleep(RPC_LATENCY).await;
+
+    let context = json!({ "slot": 1 });
+    let result = match body["method"].as_str().unwrap_or_default() {
+        "getLatestBlockhash" => json!({
+            "context": context,
+            "value": { "blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": 300 },
+        }),
+        "getBlockHeight" => json!(150),
+        "getVersion" => json!({ "solana-core": "1.16.25", "feature-set": 0 }),
+        "getAccountInfo" => json!({
+            "context": context,
+            "value": {
+                "data": [profile.as_str(), "base64"],
+                "executable": false,
+                "lamports": 1_000_000,
+                "owner": PROGRAM_ID,
+                "rentEpoch": 0,
+            },
+        }),
+        method => {
+            return HttpResponse::Ok().json(json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+                "id": body["id"],
+            }))
+        }
+    };
+
+    HttpResponse::Ok().json(json!({ "jsonrpc": "2.0", "result": result, "id": body["id"] }))
+}
+
+fn start_mock_rpc(authority: Pubkey) -> String {
+    let mut data = Vec::new();
+    UserProfile { version: UserProfile::VERSION, authority, todo_count: 3, last_todo_id: 3 }
+        .try_serialize(&mut data)
+        .expect("serialize user profile");
+    let profile = web::Data::new(base64::engine::general_purpose::STANDARD.encode(data));
+
+    let (url_tx, url_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        actix_web::rt::System::new().block_on(async move {
+            let server = HttpServer::new(move || {
+                App::new()
+                    .app_data(profile.clone())
+                    .route("/", web::post().to(rpc_handler))
+            })
+            .bind(("127.0.0.1", 0))
+            .expect("bind mock RPC server");
+
+            url_tx
+                .send(format!("http://{}", server.addrs()[0]))
+                .expect("report mock RPC address");
+            server.run().await
+        })
+    });
+
+    url_rx.recv().expect("mock RPC server started")
+}
+
+// The pre-nonblocking path: every profile lookup built its own blocking
+// client on the blocking pool. The blockhash comes from the same cache the
+// service uses, so only the RPC client strategy differs between the runs.
+async fn prepare_legacy(
+    service: SolanaService,
+    rpc_url: String,
+    program_id: Pubkey,
+    authority: Pubkey,
+) -> Transaction {
+    let profile = tokio::task::spawn_blocking(move || {
+        let rpc_client = solana_client::rpc_client::RpcClient::new_with_commitment(
+            rpc_url,
+            CommitmentConfig::confirmed(),
+        );
+        let (profile_pda, _) = pda::user_profile_address(&authority, &program_id);
+        let account = rpc_client
+            .get_account_with_commitment(&profile_pda, rpc_client.commitment())
+            .expect("fetch user profile")
+            .value
+            .expect("user profile exists");
+
+        UserProfile::try_deserialize(&mut account.data.as_slice()).expect("decode user profile")
+    })
+    .await
+    .expect("blocking task");
+    let blockhash = service.latest_blockhash().await.expect("fetch blockhash").blockhash;
+
+    let todo_id = profile.last_todo_id + 1;
+    let (user_profile, _) = pda::user_profile_address(&authority, &program_id);
+    let (todo_account, _) = pda::todo_address(&authority, todo_id, &program_id);
+    let instruction = Instruction {
+        program_id,
+        accounts: solana_todo::accounts::CreateTodo {
+            user_profile,
+            todo_account,
+            authority,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: solana_todo::instruction::CreateTodo {
+            description: "Benchmark todo".to_string(),
+            due_date: 4_102_444_800,
+        }
+        .data(),
+    };
+
+    Transaction::new_unsigned(Message::new_with_blockhash(&[instruction], Some(&authority), &blockhash))
+}
+
+async fn run<F, Fut>(label: &str, prepare: F)
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(CONCURRENCY));
+    let started = Instant::now();
+
+    let mut handles = Vec::with_capacity(REQUESTS);
+    for _ in 0..REQUESTS {
+        let permit = semaphore.clone().acquire_owned().await.expect("semaphore closed");
+        let request = prepare();
+        handles.push(tokio::spawn(async move {
+            request.await;
+            drop(permit);
+        }));
+    }
+    for handle in handles {
+        handle.await.expect("prepare task panicked");
+    }
+
+    let elapsed = started.elapsed();
+    println!(
+        "{:<28} {:>6} requests in {:>8.2?} ({:>8.1} req/s)",
+        label,
+        REQUESTS,
+        elapsed,
+        REQUESTS as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let authority = Pubkey::new_unique();
+    let program_id = Pubkey::from_str(PROGRAM_ID).expect("valid program id");
+    let rpc_url = start_mock_rpc(authority);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("build runtime");
+
+    runtime.block_on(async {
+        println!("prepare_create_todo_transaction, concurrency {}", CONCURRENCY);
+
+        let service = SolanaService::from_config(&SolanaConfig {
+            rpc_url: rpc_url.clone(),
+            program_id: PROGRAM_ID.to_string(),
+            commitment: "confirmed".to_string(),
+            backend: ChainBackend::Rpc,
+        })
+        .expect("create service");
+        let todo_service = TodoService::new(Arc::new(service.clone()));
+        let public_key = authority.to_string();
+
+        run("blocking client per request", || {
+            let request = prepare_legacy(service.clone(), rpc_url.clone(), program_id, authority);
+            async move {
+                request.await;
+            }
+        })
+        .await;
+
+        run("shared nonblocking client", || {
+            let todo_service = todo_service.clone();
+            let public_key = public_key.clone();
+            async move {
+                todo_service
+                    .prepare_create_transaction(
+                        &public_key,
+                        CreateTodoRequest {
+                            description: "Benchmark todo".to_string(),
+                            due_date: 4_102_444_800,
+                        },
+                    )
+                    .await
+                    .expect("prepare transaction");
+            }
+        })
+        .await;
+    });
+}
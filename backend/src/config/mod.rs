@@ -1,4 +1,5 @@
 use std::env;
+use std::str::FromStr;
 use std::sync::OnceLock;
 use dotenv::dotenv;
 use log::info;
@@ -22,6 +23,27 @@ pub struct SolanaConfig {
     pub rpc_url: String,
     pub program_id: String,
     pub commitment: String,
+    pub backend: ChainBackend,
+}
+
+// Which chain the services talk to: a real RPC node, or an in-process bank
+// running the todo program for local development and tests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainBackend {
+    Rpc,
+    InMemory,
+}
+
+impl FromStr for ChainBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rpc" => Ok(ChainBackend::Rpc),
+            "memory" => Ok(ChainBackend::InMemory),
+            other => Err(format!("unknown chain backend '{}', expected 'rpc' or 'memory'", other)),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -51,6 +73,10 @@ pub fn get_config() -> &'static Config {
                 rpc_url: env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "http://localhost:8899".to_string()),
                 program_id: env::var("SOLANA_PROGRAM_ID").expect("SOLANA_PROGRAM_ID must be set"),
                 commitment: env::var("SOLANA_COMMITMENT").unwrap_or_else(|_| "confirmed".to_string()),
+                backend: env::var("SOLANA_BACKEND")
+                    .unwrap_or_else(|_| "rpc".to_string())
+                    .parse()
+                    .expect("SOLANA_BACKEND must be 'rpc' or 'memory'"),
             },
             jwt: JwtConfig {
                 secret: env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
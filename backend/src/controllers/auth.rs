@@ -1,10 +1,9 @@
 use actix_web::{post, web, HttpResponse};
 use log::info;
-use utoipa::OpenApi;
 
 use crate::{
     error::AppResult,
-    models::auth::{AuthRequest, AuthResponse},
+    models::auth::AuthRequest,
     services::auth::AuthService,
 };
 
@@ -1,12 +1,11 @@
 use actix_web::{delete, get, post, put, web, HttpMessage, HttpRequest, HttpResponse};
-use log::{error, info};
-use utoipa::OpenApi;
+use log::info;
 
 use crate::{
     error::AppResult,
     models::{
         auth::AuthToken,
-        todo::{CreateTodoRequest, TodoResponse, UpdateTodoRequest},
+        todo::{CreateTodoRequest, TodoQuery, UpdateTodoRequest},
     },
     services::todo::TodoService,
 };
@@ -14,6 +13,10 @@ use crate::{
 #[utoipa::path(
     get,
     path = "/api/todos",
+    params(
+        ("tag" = Option<String>, Query, description = "Only todos carrying this tag"),
+        ("priority" = Option<u8>, Query, description = "Only todos with this priority")
+    ),
     security(("bearer_auth" = [])),
     responses(
         (status = 200, description = "List of todos", body = Vec<TodoResponse>),
@@ -26,13 +29,15 @@ use crate::{
 pub async fn get_todos(
     req: HttpRequest,
     todo_service: web::Data<TodoService>,
+    query: web::Query<TodoQuery>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     
     info!("Getting todos for user: {}", auth_token.public_key);
     
-    let todos = todo_service.get_todos(&auth_token.public_key).await?;
+    let todos = todo_service
+        .get_todos(&auth_token.public_key, &query.into_inner())
+        .await?;
     
     Ok(HttpResponse::Ok().json(todos))
 }
@@ -56,8 +61,7 @@ pub async fn create_todo(
     todo_service: web::Data<TodoService>,
     todo_request: web::Json<CreateTodoRequest>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     
     info!("Creating todo for user: {}", auth_token.public_key);
     
@@ -92,8 +96,7 @@ pub async fn update_todo(
     todo_service: web::Data<TodoService>,
     update_request: web::Json<UpdateTodoRequest>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     let todo_id = path.into_inner();
     
     info!("Updating todo {} for user: {}", todo_id, auth_token.public_key);
@@ -126,8 +129,7 @@ pub async fn delete_todo(
     path: web::Path<u64>,
     todo_service: web::Data<TodoService>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     let todo_id = path.into_inner();
     
     info!("Deleting todo {} for user: {}", todo_id, auth_token.public_key);
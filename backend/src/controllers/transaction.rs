@@ -1,17 +1,48 @@
-use actix_web::{post, web, HttpMessage, HttpRequest, HttpResponse};
-use log::{error, info};
-use utoipa::OpenApi;
+use actix_web::{get, post, web, HttpMessage, HttpRequest, HttpResponse};
+use log::info;
 
 use crate::{
     error::AppResult,
     models::{
         auth::AuthToken,
         todo::{CreateTodoRequest, UpdateTodoRequest},
-        transaction::{DeleteTodoTransactionRequest, PreparedTransaction, SignedTransaction},
+        transaction::{
+            AcceptTransferTransactionRequest, CancelTransferTransactionRequest, CreateTodosBatchTransactionRequest,
+            DelegateTransactionRequest, DeleteTodoTransactionRequest, OfferTransferTransactionRequest,
+            RevokeDelegationTransactionRequest, SignedTransaction, SubmitTransactionQuery, TodoOwnerQuery,
+        },
     },
     services::todo::TodoService,
 };
 
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/initialize",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
+        (status = 400, description = "User profile is already initialized"),
+        (status = 401, description = "Unauthorized"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/initialize")]
+pub async fn prepare_initialize_transaction(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing initialize transaction for user: {}", auth_token.public_key);
+    
+    let prepared_transaction = todo_service
+        .prepare_initialize_transaction(&auth_token.public_key)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transaction))
+}
+
 #[utoipa::path(
     post,
     path = "/api/transactions/prepare/create",
@@ -31,8 +62,7 @@ pub async fn prepare_create_transaction(
     todo_service: web::Data<TodoService>,
     todo_request: web::Json<CreateTodoRequest>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     
     info!("Preparing create transaction for user: {}", auth_token.public_key);
     
@@ -43,11 +73,46 @@ pub async fn prepare_create_transaction(
     Ok(HttpResponse::Ok().json(prepared_transaction))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/create-batch",
+    request_body = CreateTodosBatchTransactionRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transactions prepared successfully, to be signed and submitted in order, each confirmed before the next", body = [PreparedTransaction]),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/create-batch")]
+pub async fn prepare_create_batch_transactions(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+    batch_request: web::Json<CreateTodosBatchTransactionRequest>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!(
+        "Preparing batch create transactions for {} todos and user: {}",
+        batch_request.todos.len(),
+        auth_token.public_key
+    );
+    
+    let prepared_transactions = todo_service
+        .prepare_create_batch_transactions(&auth_token.public_key, batch_request.into_inner().todos)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transactions))
+}
+
 #[utoipa::path(
     post,
     path = "/api/transactions/prepare/update/{id}",
     params(
-        ("id" = u64, Path, description = "Todo ID")
+        ("id" = u64, Path, description = "Todo ID"),
+        ("owner" = Option<String>, Query, description = "Owner of the todo, when it was delegated to the signer")
     ),
     request_body = UpdateTodoRequest,
     security(("bearer_auth" = [])),
@@ -55,6 +120,8 @@ pub async fn prepare_create_transaction(
         (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
         (status = 400, description = "Bad request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "The delegation does not permit this change"),
+        (status = 404, description = "No delegation from this owner"),
         (status = 429, description = "Rate limit exceeded"),
         (status = 500, description = "Internal server error")
     )
@@ -63,17 +130,22 @@ pub async fn prepare_create_transaction(
 pub async fn prepare_update_transaction(
     req: HttpRequest,
     path: web::Path<u64>,
+    query: web::Query<TodoOwnerQuery>,
     todo_service: web::Data<TodoService>,
     update_request: web::Json<UpdateTodoRequest>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     let todo_id = path.into_inner();
     
     info!("Preparing update transaction for todo {} and user: {}", todo_id, auth_token.public_key);
     
     let prepared_transaction = todo_service
-        .prepare_update_transaction(&auth_token.public_key, todo_id, update_request.into_inner())
+        .prepare_update_transaction(
+            &auth_token.public_key,
+            todo_id,
+            update_request.into_inner(),
+            query.owner.as_deref(),
+        )
         .await?;
     
     Ok(HttpResponse::Ok().json(prepared_transaction))
@@ -88,6 +160,8 @@ pub async fn prepare_update_transaction(
         (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
         (status = 400, description = "Bad request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "The delegation does not permit this change"),
+        (status = 404, description = "No delegation from this owner"),
         (status = 429, description = "Rate limit exceeded"),
         (status = 500, description = "Internal server error")
     )
@@ -98,14 +172,225 @@ pub async fn prepare_delete_transaction(
     todo_service: web::Data<TodoService>,
     delete_request: web::Json<DeleteTodoTransactionRequest>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     let todo_id = delete_request.todo_id;
     
     info!("Preparing delete transaction for todo {} and user: {}", todo_id, auth_token.public_key);
     
     let prepared_transaction = todo_service
-        .prepare_delete_transaction(&auth_token.public_key, todo_id)
+        .prepare_delete_transaction(&auth_token.public_key, todo_id, delete_request.owner.as_deref())
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transaction))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/close",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transactions prepared successfully, to be signed and submitted in order", body = [PreparedTransaction]),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "User profile not found"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/close")]
+pub async fn prepare_close_user_transactions(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing close transactions for user: {}", auth_token.public_key);
+    
+    let prepared_transactions = todo_service
+        .prepare_close_user_transactions(&auth_token.public_key)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transactions))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/clear-completed",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transactions prepared successfully, to be signed and submitted in order", body = [PreparedTransaction]),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "User profile not found"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/clear-completed")]
+pub async fn prepare_clear_completed_transactions(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing clear completed transactions for user: {}", auth_token.public_key);
+    
+    let prepared_transactions = todo_service
+        .prepare_clear_completed_transactions(&auth_token.public_key)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transactions))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/delegate",
+    request_body = DelegateTransactionRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/delegate")]
+pub async fn prepare_delegate_transaction(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+    delegate_request: web::Json<DelegateTransactionRequest>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing delegate transaction for {} and user: {}", delegate_request.delegate, auth_token.public_key);
+    
+    let prepared_transaction = todo_service
+        .prepare_delegate_transaction(&auth_token.public_key, &delegate_request.delegate, &delegate_request.permissions)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transaction))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/revoke-delegation",
+    request_body = RevokeDelegationTransactionRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Delegation not found"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/revoke-delegation")]
+pub async fn prepare_revoke_delegation_transaction(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+    revoke_request: web::Json<RevokeDelegationTransactionRequest>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing revoke delegation transaction for {} and user: {}", revoke_request.delegate, auth_token.public_key);
+    
+    let prepared_transaction = todo_service
+        .prepare_revoke_delegation_transaction(&auth_token.public_key, &revoke_request.delegate)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transaction))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/offer-transfer",
+    request_body = OfferTransferTransactionRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Todo not found"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/offer-transfer")]
+pub async fn prepare_offer_transfer_transaction(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+    offer_request: web::Json<OfferTransferTransactionRequest>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing offer transfer transaction for todo {} to {} and user: {}", offer_request.todo_id, offer_request.recipient, auth_token.public_key);
+    
+    let prepared_transaction = todo_service
+        .prepare_offer_transfer_transaction(&auth_token.public_key, offer_request.todo_id, &offer_request.recipient)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transaction))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/accept-transfer",
+    request_body = AcceptTransferTransactionRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No transfer offer for this todo"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/accept-transfer")]
+pub async fn prepare_accept_transfer_transaction(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+    accept_request: web::Json<AcceptTransferTransactionRequest>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing accept transfer transaction for todo {} of {} and user: {}", accept_request.todo_id, accept_request.owner, auth_token.public_key);
+    
+    let prepared_transaction = todo_service
+        .prepare_accept_transfer_transaction(&auth_token.public_key, &accept_request.owner, accept_request.todo_id)
+        .await?;
+    
+    Ok(HttpResponse::Ok().json(prepared_transaction))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/transactions/prepare/cancel-transfer",
+    request_body = CancelTransferTransactionRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Transaction prepared successfully", body = PreparedTransaction),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No transfer offer for this todo"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/prepare/cancel-transfer")]
+pub async fn prepare_cancel_transfer_transaction(
+    req: HttpRequest,
+    todo_service: web::Data<TodoService>,
+    cancel_request: web::Json<CancelTransferTransactionRequest>,
+) -> AppResult<HttpResponse> {
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
+    
+    info!("Preparing cancel transfer transaction for todo {} and user: {}", cancel_request.todo_id, auth_token.public_key);
+    
+    let prepared_transaction = todo_service
+        .prepare_cancel_transfer_transaction(&auth_token.public_key, cancel_request.todo_id, cancel_request.owner.as_deref())
         .await?;
     
     Ok(HttpResponse::Ok().json(prepared_transaction))
@@ -114,12 +399,16 @@ pub async fn prepare_delete_transaction(
 #[utoipa::path(
     post,
     path = "/api/transactions/submit",
+    params(
+        ("wait_for" = Option<ConfirmationLevel>, Query, description = "Block until the transaction reaches this commitment")
+    ),
     request_body = SignedTransaction,
     security(("bearer_auth" = [])),
     responses(
-        (status = 200, description = "Transaction submitted successfully", body = String),
-        (status = 400, description = "Bad request"),
+        (status = 200, description = "Transaction submitted successfully", body = TransactionStatusResponse),
+        (status = 400, description = "Invalid, unsigned or foreign transaction"),
         (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Transaction is stale and must be prepared again"),
         (status = 429, description = "Rate limit exceeded"),
         (status = 500, description = "Internal server error")
     )
@@ -128,16 +417,46 @@ pub async fn prepare_delete_transaction(
 pub async fn submit_transaction(
     req: HttpRequest,
     todo_service: web::Data<TodoService>,
+    query: web::Query<SubmitTransactionQuery>,
     signed_transaction: web::Json<SignedTransaction>,
 ) -> AppResult<HttpResponse> {
-    let extensions = req.extensions();
-    let auth_token = extensions.get::<AuthToken>().unwrap();
+    let auth_token = req.extensions().get::<AuthToken>().cloned().unwrap();
     
     info!("Submitting signed transaction for user: {}", auth_token.public_key);
     
-    let signature = todo_service
-        .submit_transaction(signed_transaction.into_inner())
+    let status = todo_service
+        .submit_transaction(&auth_token.public_key, signed_transaction.into_inner(), query.wait_for)
         .await?;
     
-    Ok(HttpResponse::Ok().json(serde_json::json!({ "signature": signature })))
-} 
\ No newline at end of file
+    Ok(HttpResponse::Ok().json(status))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/transactions/{signature}",
+    params(
+        ("signature" = String, Path, description = "Transaction signature")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Current transaction status", body = TransactionStatusResponse),
+        (status = 400, description = "Invalid signature"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Transaction not found"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/{signature}")]
+pub async fn get_transaction_status(
+    path: web::Path<String>,
+    todo_service: web::Data<TodoService>,
+) -> AppResult<HttpResponse> {
+    let signature = path.into_inner();
+    
+    info!("Getting status for transaction: {}", signature);
+    
+    let status = todo_service.get_transaction_status(&signature).await?;
+    
+    Ok(HttpResponse::Ok().json(status))
+}
@@ -13,6 +13,15 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+
+    #[error("Stale transaction: {0}")]
+    StaleTransaction(String),
+
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
@@ -38,6 +47,9 @@ impl ResponseError for AppError {
             AppError::Auth(_) => StatusCode::UNAUTHORIZED,
             AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::InvalidTransaction(_) => StatusCode::BAD_REQUEST,
+            AppError::StaleTransaction(_) => StatusCode::CONFLICT,
             AppError::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
             AppError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::SolanaError(_) => StatusCode::BAD_GATEWAY,
@@ -65,6 +77,10 @@ impl AppError {
         AppError::NotFound(msg.to_string())
     }
 
+    pub fn forbidden<T: ToString>(msg: T) -> Self {
+        AppError::Forbidden(msg.to_string())
+    }
+
     pub fn auth<T: ToString>(msg: T) -> Self {
         AppError::Auth(msg.to_string())
     }
@@ -73,6 +89,14 @@ impl AppError {
         AppError::InternalServerError(msg.to_string())
     }
 
+    pub fn invalid_transaction<T: ToString>(msg: T) -> Self {
+        AppError::InvalidTransaction(msg.to_string())
+    }
+
+    pub fn stale_transaction<T: ToString>(msg: T) -> Self {
+        AppError::StaleTransaction(msg.to_string())
+    }
+
     pub fn solana<T: ToString>(msg: T) -> Self {
         AppError::SolanaError(msg.to_string())
     }
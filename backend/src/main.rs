@@ -9,22 +9,18 @@ use log::info;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-mod config;
-mod controllers;
-mod error;
-mod middlewares;
-mod models;
-mod services;
-mod utils;
-
-use crate::{
+use solana_todo_backend::{
     config::get_config,
     controllers::{
         authenticate, create_todo, delete_todo, get_todos, update_todo,
-        prepare_create_transaction, prepare_update_transaction, prepare_delete_transaction, submit_transaction
+        prepare_initialize_transaction, prepare_create_transaction, prepare_create_batch_transactions, prepare_update_transaction, prepare_delete_transaction,
+        prepare_clear_completed_transactions, prepare_close_user_transactions, prepare_delegate_transaction,
+        prepare_revoke_delegation_transaction, prepare_offer_transfer_transaction, prepare_accept_transfer_transaction,
+        prepare_cancel_transfer_transaction, submit_transaction,
+        get_transaction_status
     },
     middlewares::{Authentication, RateLimit},
-    services::{AuthService, SolanaService, TodoService},
+    services::{connect, AuthService, TodoService},
     utils::ApiDoc,
 };
 
@@ -38,9 +34,9 @@ async fn main() -> std::io::Result<()> {
     info!("Starting server at {}:{}", config.server.host, config.server.port);
 
     // Initialize services
-    let solana_service = SolanaService::new().expect("Failed to initialize Solana service");
-    let todo_service = TodoService::new(solana_service.clone());
-    let auth_service = AuthService::new(solana_service);
+    let chain = connect(&config.solana).await.expect("Failed to initialize Solana service");
+    let todo_service = TodoService::new(chain.clone());
+    let auth_service = AuthService::new(chain);
 
     // Create the server
     HttpServer::new(move || {
@@ -81,10 +77,20 @@ async fn main() -> std::io::Result<()> {
                         web::scope("/transactions")
                             .wrap(RateLimit::new())
                             .wrap(Authentication::new(auth_service.clone()))
+                            .service(prepare_initialize_transaction)
                             .service(prepare_create_transaction)
+                            .service(prepare_create_batch_transactions)
                             .service(prepare_update_transaction)
                             .service(prepare_delete_transaction)
+                            .service(prepare_close_user_transactions)
+                            .service(prepare_clear_completed_transactions)
+                            .service(prepare_delegate_transaction)
+                            .service(prepare_revoke_delegation_transaction)
+                            .service(prepare_offer_transfer_transaction)
+                            .service(prepare_accept_transfer_transaction)
+                            .service(prepare_cancel_transfer_transaction)
                             .service(submit_transaction)
+                            .service(get_transaction_status)
                     )
             )
     })
@@ -7,11 +7,7 @@ use actix_web::{
 use futures::future::LocalBoxFuture;
 use log::error;
 
-use crate::{
-    error::AppError,
-    models::auth::AuthToken,
-    services::auth::AuthService,
-};
+use crate::{error::AppError, services::auth::AuthService};
 
 pub struct Authentication {
     auth_service: AuthService,
@@ -66,13 +62,8 @@ where
             .headers()
             .get("Authorization")
             .and_then(|header| header.to_str().ok())
-            .and_then(|auth_header| {
-                if auth_header.starts_with("Bearer ") {
-                    Some(auth_header[7..].to_string())
-                } else {
-                    None
-                }
-            });
+            .and_then(|auth_header| auth_header.strip_prefix("Bearer "))
+            .map(|token| token.to_string());
 
         // If no token is found, return a 401 Unauthorized error
         let token = match token {
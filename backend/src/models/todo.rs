@@ -1,7 +1,7 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use solana_todo::{TodoItem, TodoStatus as ProgramTodoStatus};
 use utoipa::ToSchema;
-use uuid::Uuid;
 
 // Create a newtype wrapper for DateTime<Utc>
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -22,6 +22,47 @@ impl From<DateTimeWrapper> for DateTime<Utc> {
     }
 }
 
+// Workflow status of a todo, mirroring the program's TodoStatus
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl TodoStatus {
+    pub fn is_completed(&self) -> bool {
+        *self == TodoStatus::Done
+    }
+}
+
+impl From<ProgramTodoStatus> for TodoStatus {
+    fn from(status: ProgramTodoStatus) -> Self {
+        match status {
+            ProgramTodoStatus::Todo => TodoStatus::Todo,
+            ProgramTodoStatus::InProgress => TodoStatus::InProgress,
+            ProgramTodoStatus::Blocked => TodoStatus::Blocked,
+            ProgramTodoStatus::Done => TodoStatus::Done,
+            ProgramTodoStatus::Cancelled => TodoStatus::Cancelled,
+        }
+    }
+}
+
+impl From<TodoStatus> for ProgramTodoStatus {
+    fn from(status: TodoStatus) -> Self {
+        match status {
+            TodoStatus::Todo => ProgramTodoStatus::Todo,
+            TodoStatus::InProgress => ProgramTodoStatus::InProgress,
+            TodoStatus::Blocked => ProgramTodoStatus::Blocked,
+            TodoStatus::Done => ProgramTodoStatus::Done,
+            TodoStatus::Cancelled => ProgramTodoStatus::Cancelled,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Todo {
     #[schema(example = "1")]
@@ -30,6 +71,10 @@ pub struct Todo {
     #[schema(example = "Complete the Solana project")]
     pub description: String,
     
+    #[schema(example = "in_progress")]
+    pub status: TodoStatus,
+
+    // Whether the status is Done, for clients that predate statuses
     #[schema(example = "false")]
     pub completed: bool,
     
@@ -38,6 +83,40 @@ pub struct Todo {
     
     #[schema(example = "HXtBm8XZbxaTt41uqaKhwUAa6Z1aPyvJdsZVENiWsetg")]
     pub owner: String,
+
+    // Unix timestamps recorded by the program; 0 if never recorded
+    #[schema(example = "1625011200")]
+    pub created_at: i64,
+
+    #[schema(example = "1625011200")]
+    pub updated_at: i64,
+
+    #[schema(example = "1625097600")]
+    pub completed_at: Option<i64>,
+
+    #[schema(example = "2")]
+    pub priority: u8,
+
+    #[schema(example = json!(["work", "urgent"]))]
+    pub tags: Vec<String>,
+}
+
+impl From<TodoItem> for Todo {
+    fn from(item: TodoItem) -> Self {
+        Self {
+            id: item.id,
+            description: item.description,
+            status: item.status.into(),
+            completed: item.status.is_completed(),
+            due_date: item.due_date,
+            owner: item.owner.to_string(),
+            created_at: item.created_at,
+            updated_at: item.updated_at,
+            completed_at: item.completed_at,
+            priority: item.priority,
+            tags: item.tags,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -54,8 +133,37 @@ pub struct UpdateTodoRequest {
     #[schema(example = "Complete the Solana project updated", min_length = 1, max_length = 280)]
     pub description: Option<String>,
     
+    #[schema(example = "in_progress")]
+    pub status: Option<TodoStatus>,
+
+    /// Shorthand for `status`: true means done, false means back to todo
     #[schema(example = "true")]
     pub completed: Option<bool>,
+
+    #[schema(example = "1625184000")]
+    pub due_date: Option<i64>,
+
+    /// From 0 (none) to 3 (highest)
+    #[schema(example = "2", maximum = 3)]
+    pub priority: Option<u8>,
+
+    /// Replaces every tag; up to 5 unique tags of 1 to 20 UTF-8 bytes
+    #[schema(example = json!(["work", "urgent"]))]
+    pub tags: Option<Vec<String>>,
+}
+
+// Filters for listing todos
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TodoQuery {
+    pub tag: Option<String>,
+    pub priority: Option<u8>,
+}
+
+impl TodoQuery {
+    pub fn matches(&self, todo: &TodoResponse) -> bool {
+        self.tag.as_ref().is_none_or(|tag| todo.tags.contains(tag))
+            && self.priority.is_none_or(|priority| todo.priority == priority)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -66,6 +174,10 @@ pub struct TodoResponse {
     #[schema(example = "Complete the Solana project")]
     pub description: String,
     
+    #[schema(example = "in_progress")]
+    pub status: TodoStatus,
+
+    /// Whether the status is done; kept for clients that predate statuses
     #[schema(example = "false")]
     pub completed: bool,
     
@@ -75,9 +187,29 @@ pub struct TodoResponse {
     #[schema(example = "HXtBm8XZbxaTt41uqaKhwUAa6Z1aPyvJdsZVENiWsetg")]
     pub owner: String,
     
-    pub created_at: DateTimeWrapper,
+    /// When the todo was created; absent for todos that predate on-chain timestamps
+    pub created_at: Option<DateTimeWrapper>,
     
+    /// When the todo was last changed; absent for todos that predate on-chain timestamps
     pub updated_at: Option<DateTimeWrapper>,
+
+    /// When the todo was marked completed; absent while it is open
+    pub completed_at: Option<DateTimeWrapper>,
+
+    /// From 0 (none) to 3 (highest)
+    #[schema(example = "2")]
+    pub priority: u8,
+
+    #[schema(example = json!(["work", "urgent"]))]
+    pub tags: Vec<String>,
+}
+
+// Convert an on-chain Unix timestamp, treating 0 as "never recorded"
+fn recorded_at(timestamp: i64) -> Option<DateTimeWrapper> {
+    if timestamp == 0 {
+        return None;
+    }
+    Utc.timestamp_opt(timestamp, 0).single().map(DateTimeWrapper)
 }
 
 impl From<Todo> for TodoResponse {
@@ -85,11 +217,15 @@ impl From<Todo> for TodoResponse {
         Self {
             id: todo.id,
             description: todo.description,
+            status: todo.status,
             completed: todo.completed,
             due_date: todo.due_date,
             owner: todo.owner,
-            created_at: DateTimeWrapper(Utc::now()), // Wrap the DateTime
-            updated_at: None,
+            created_at: recorded_at(todo.created_at),
+            updated_at: recorded_at(todo.updated_at),
+            completed_at: todo.completed_at.and_then(recorded_at),
+            priority: todo.priority,
+            tags: todo.tags,
         }
     }
 } 
\ No newline at end of file
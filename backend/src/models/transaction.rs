@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use solana_todo::Delegation;
 use utoipa::ToSchema;
 
+use crate::models::todo::{CreateTodoRequest, DateTimeWrapper, TodoStatus};
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct PreparedTransaction {
     #[schema(example = "AQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAAEDyjtJ6Jf8zd9P/YoVvMM9Yg9n/vYMzjBFtKyI5IVwBGNBxiqYhI0UXeG9LrYPk4p8lZCLXKJTa6nQ61KJ1gAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA4O5d4OTMrMvbCpVv5maDe82SnNxl6OYOHAMBIwXxF0PS7UOi9/tQYZcG9zV9k5OTcUXJIcI+iRGhcGQly3yZAgAAAAAHYUJnTxU6m3Fck70YwVzLpNcbJ0yKVpzQzLcR7y9ZVQEDAwABAEoAAAAAAAAAjbv2dF80rSTn3xTrH9Y1XRdgFxvLhd+jN2gDBQD/GfwMjinlkDl90P7xO3YKBp9BQkM/fqGcJ5aoVaA8tzoBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")]
@@ -11,6 +14,13 @@ pub struct PreparedTransaction {
 
     #[schema(example = "{\"todo_id\":1,\"description\":\"Buy groceries\"}")]
     pub metadata: Option<String>,
+
+    /// Last block height at which the embedded blockhash is still accepted
+    #[schema(example = "245082315")]
+    pub last_valid_block_height: u64,
+
+    /// Estimated time after which the transaction must be prepared again
+    pub expires_at: DateTimeWrapper,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -22,6 +32,85 @@ pub struct SignedTransaction {
     pub serialized_transaction: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Pending,
+    Processed,
+    Confirmed,
+    Finalized,
+    Failed,
+    Expired,
+}
+
+impl TransactionStatus {
+    // Whether the status can no longer change
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TransactionStatus::Finalized | TransactionStatus::Failed | TransactionStatus::Expired
+        )
+    }
+
+    // Whether the transaction has landed with at least the given commitment
+    pub fn satisfies(&self, level: ConfirmationLevel) -> bool {
+        match self {
+            TransactionStatus::Processed => level == ConfirmationLevel::Processed,
+            TransactionStatus::Confirmed => level != ConfirmationLevel::Finalized,
+            TransactionStatus::Finalized => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationLevel {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct ProgramErrorDetails {
+    #[schema(example = "6000")]
+    pub code: u32,
+
+    #[schema(example = "DescriptionTooLong")]
+    pub name: String,
+
+    #[schema(example = "Description must be 280 characters or less")]
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TransactionStatusResponse {
+    #[schema(example = "3WZ4sBJm5eGzRfRXLCuUktaE7xMW25dveKfpGxCCkyvMfPQiJKgf1nuFCvhKTQUCHYKGEE6KuQnUijBTKMhNcWd7")]
+    pub signature: String,
+
+    #[schema(example = "confirmed")]
+    pub status: TransactionStatus,
+
+    #[schema(example = "245082100")]
+    pub slot: Option<u64>,
+
+    #[schema(example = "Error processing Instruction 0: custom program error: 0x1770")]
+    pub error: Option<String>,
+
+    pub program_error: Option<ProgramErrorDetails>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubmitTransactionQuery {
+    pub wait_for: Option<ConfirmationLevel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TodoOwnerQuery {
+    /// Owner of the todo, when it was delegated to the signer
+    pub owner: Option<String>,
+}
+
 // Request for preparing transactions
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct CreateTodoTransactionRequest {
@@ -37,12 +126,98 @@ pub struct UpdateTodoTransactionRequest {
     #[schema(example = "Complete the Solana project updated")]
     pub description: Option<String>,
     
+    #[schema(example = "in_progress")]
+    pub status: Option<TodoStatus>,
+
     #[schema(example = "true")]
     pub completed: Option<bool>,
+
+    #[schema(example = "1625184000")]
+    pub due_date: Option<i64>,
+
+    #[schema(example = "2")]
+    pub priority: Option<u8>,
+
+    #[schema(example = json!(["work", "urgent"]))]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CreateTodosBatchTransactionRequest {
+    pub todos: Vec<CreateTodoRequest>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct DeleteTodoTransactionRequest {
     #[schema(example = "1")]
     pub todo_id: u64,
+
+    /// Owner of the todo, when it was delegated to the signer
+    #[schema(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    pub owner: Option<String>,
+}
+
+// A change a collaborator can be allowed to make to the owner's todos
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DelegatePermission {
+    SetStatus,
+    EditDescription,
+    Delete,
+}
+
+impl DelegatePermission {
+    // The program's flag for the permission
+    pub fn bit(&self) -> u8 {
+        match self {
+            DelegatePermission::SetStatus => Delegation::SET_STATUS,
+            DelegatePermission::EditDescription => Delegation::EDIT_DESCRIPTION,
+            DelegatePermission::Delete => Delegation::DELETE,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct DelegateTransactionRequest {
+    #[schema(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    pub delegate: String,
+
+    #[schema(example = json!(["set_status", "edit_description"]))]
+    pub permissions: Vec<DelegatePermission>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RevokeDelegationTransactionRequest {
+    #[schema(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    pub delegate: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OfferTransferTransactionRequest {
+    #[schema(example = "1")]
+    pub todo_id: u64,
+
+    #[schema(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    pub recipient: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AcceptTransferTransactionRequest {
+    /// Wallet that offered the todo
+    #[schema(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    pub owner: String,
+
+    /// The todo's id under the offering wallet
+    #[schema(example = "1")]
+    pub todo_id: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CancelTransferTransactionRequest {
+    #[schema(example = "1")]
+    pub todo_id: u64,
+
+    /// Owner of the todo, when the signer is declining an offer made to them
+    #[schema(example = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")]
+    pub owner: Option<String>,
 } 
\ No newline at end of file
@@ -1,23 +1,23 @@
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use log::{error, info};
 
 use crate::{
     config::get_config,
     error::AppError,
     models::auth::{AuthRequest, AuthResponse, AuthToken, Claims},
-    services::solana::SolanaService,
+    services::chain::ChainClient,
 };
 
 #[derive(Clone)]
 pub struct AuthService {
-    solana_service: SolanaService,
+    chain: Arc<dyn ChainClient>,
 }
 
 impl AuthService {
-    pub fn new(solana_service: SolanaService) -> Self {
-        Self { solana_service }
+    pub fn new(chain: Arc<dyn ChainClient>) -> Self {
+        Self { chain }
     }
 
     // Generate a JWT token for a user
@@ -46,7 +46,7 @@ impl AuthService {
 
         // 3. Verify the signature
         let is_valid = self
-            .solana_service
+            .chain
             .verify_signature(&public_key, &message, &signature)?;
 
         if !is_valid {
@@ -0,0 +1,146 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anchor_client::solana_sdk::{
+    account::Account,
+    clock::DEFAULT_MS_PER_SLOT,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+#[cfg(test)]
+use mockall::automock;
+
+use crate::{
+    config::{ChainBackend, SolanaConfig},
+    error::AppError,
+    models::transaction::{ConfirmationLevel, TransactionStatusResponse},
+    services::solana::SolanaService,
+};
+#[cfg(feature = "in-memory")]
+use crate::services::in_memory::InMemoryChain;
+
+// How old a cached blockhash may get before a prepare call fetches its own
+pub const BLOCKHASH_MAX_AGE_SECS: i64 = 20;
+
+#[derive(Clone, Copy, Debug)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    pub block_height: u64,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl CachedBlockhash {
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        now - self.fetched_at < ChronoDuration::seconds(BLOCKHASH_MAX_AGE_SECS)
+    }
+
+    // Estimated time at which the blockhash stops being accepted, assuming the
+    // target slot time from the block height at fetch time
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        let remaining_blocks = self.last_valid_block_height.saturating_sub(self.block_height);
+        self.fetched_at + ChronoDuration::milliseconds((remaining_blocks * DEFAULT_MS_PER_SLOT) as i64)
+    }
+}
+
+// Outcome of running a transaction without committing it
+#[derive(Clone, Debug, Default)]
+pub struct SimulationResult {
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+}
+
+// Everything the services need from the chain. SolanaService talks to an RPC
+// node; tests and local development can plug in other implementations.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    // The todo program this client serves
+    fn program_id(&self) -> Pubkey;
+
+    // Fetch a single account, or None if it does not exist
+    async fn get_account(&self, address: Pubkey) -> Result<Option<Account>, AppError>;
+
+    // Fetch several accounts at once, with None for each address that has no account
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, AppError>;
+
+    // Fetch every account of the program whose data starts with
+    // `discriminator` and holds each of `filters`' bytes at its offset
+    async fn get_program_accounts(
+        &self,
+        discriminator: [u8; 8],
+        filters: Vec<(usize, Vec<u8>)>,
+    ) -> Result<Vec<(Pubkey, Account)>, AppError>;
+
+    // A recent blockhash to build transactions against
+    async fn latest_blockhash(&self) -> Result<CachedBlockhash, AppError>;
+
+    async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult, AppError>;
+
+    // Submit a signed transaction and start tracking its confirmation
+    async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, AppError>;
+
+    // Current status of a transaction, or None if the chain has never seen it
+    async fn get_transaction_status(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatusResponse>, AppError>;
+
+    // Block until a sent transaction reaches `level`, settles, or the wait times out
+    async fn wait_for_confirmation(
+        &self,
+        signature: &Signature,
+        level: ConfirmationLevel,
+    ) -> Result<TransactionStatusResponse, AppError>;
+
+    // Verify a signature from a Solana wallet
+    fn verify_signature(
+        &self,
+        public_key: &str,
+        message: &str,
+        signature: &str,
+    ) -> Result<bool, AppError> {
+        // Convert public key string to Pubkey
+        let pubkey = Pubkey::from_str(public_key)
+            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
+
+        // Convert signature string to Signature
+        let signature_bytes = bs58::decode(signature)
+            .into_vec()
+            .map_err(|e| AppError::bad_request(format!("Invalid signature format: {}", e)))?;
+
+        if signature_bytes.len() != 64 {
+            return Err(AppError::bad_request("Invalid signature length"));
+        }
+
+        let signature = Signature::try_from(signature_bytes.as_slice())
+            .map_err(|e| AppError::bad_request(format!("Invalid signature: {}", e)))?;
+
+        // Verify the signature
+        Ok(signature.verify(pubkey.as_ref(), message.as_bytes()))
+    }
+}
+
+// Build the chain client selected by configuration, starting any background
+// tasks it relies on
+pub async fn connect(config: &SolanaConfig) -> Result<Arc<dyn ChainClient>, AppError> {
+    match config.backend {
+        ChainBackend::Rpc => {
+            let solana_service = SolanaService::from_config(config)?;
+            solana_service.start_blockhash_refresh();
+            solana_service.start_transaction_tracker();
+            Ok(Arc::new(solana_service))
+        }
+        #[cfg(feature = "in-memory")]
+        ChainBackend::InMemory => Ok(Arc::new(InMemoryChain::from_config(config).await?)),
+        #[cfg(not(feature = "in-memory"))]
+        ChainBackend::InMemory => Err(AppError::internal(
+            "SOLANA_BACKEND=memory needs the backend built with the in-memory feature",
+        )),
+    }
+}
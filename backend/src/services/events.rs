@@ -0,0 +1,135 @@
+use anchor_client::{
+    anchor_lang::{AnchorDeserialize, Discriminator},
+    solana_sdk::pubkey::Pubkey,
+};
+use base64::{engine::general_purpose, Engine as _};
+use solana_todo::{
+    CompletedTodosDeleted, DelegationGranted, DelegationRevoked, DelegationUpdated, TodoCreated,
+    TodoDeleted, TodoDescriptionUpdated, TodoDueDateUpdated, TodoMigrated, TodoPriorityUpdated,
+    TodoStatusChanged, TodoTagsUpdated, TodoTransferCancelled, TodoTransferOffered, TodoTransferred,
+    TodosDeleted, UserClosed, UserInitialized, UserProfileMigrated,
+};
+
+// Prefix the runtime puts in front of data logged with `sol_log_data`, which
+// is what `emit!` uses
+pub(crate) const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+// An event emitted by the todo program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TodoEvent {
+    UserInitialized(UserInitialized),
+    TodoCreated(TodoCreated),
+    TodoStatusChanged(TodoStatusChanged),
+    TodoDescriptionUpdated(TodoDescriptionUpdated),
+    TodoDueDateUpdated(TodoDueDateUpdated),
+    TodoPriorityUpdated(TodoPriorityUpdated),
+    TodoTagsUpdated(TodoTagsUpdated),
+    TodoDeleted(TodoDeleted),
+    CompletedTodosDeleted(CompletedTodosDeleted),
+    TodosDeleted(TodosDeleted),
+    UserClosed(UserClosed),
+    DelegationGranted(DelegationGranted),
+    DelegationUpdated(DelegationUpdated),
+    DelegationRevoked(DelegationRevoked),
+    TodoTransferOffered(TodoTransferOffered),
+    TodoTransferred(TodoTransferred),
+    TodoTransferCancelled(TodoTransferCancelled),
+    TodoMigrated(TodoMigrated),
+    UserProfileMigrated(UserProfileMigrated),
+}
+
+impl TodoEvent {
+    // Decode an event from its discriminator-prefixed borsh encoding, or None
+    // if the bytes are not one of the program's events
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        let (discriminator, mut payload) = data.split_at(8);
+
+        let event = if discriminator == UserInitialized::DISCRIMINATOR {
+            TodoEvent::UserInitialized(UserInitialized::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoCreated::DISCRIMINATOR {
+            TodoEvent::TodoCreated(TodoCreated::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoStatusChanged::DISCRIMINATOR {
+            TodoEvent::TodoStatusChanged(TodoStatusChanged::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoDescriptionUpdated::DISCRIMINATOR {
+            TodoEvent::TodoDescriptionUpdated(TodoDescriptionUpdated::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoDueDateUpdated::DISCRIMINATOR {
+            TodoEvent::TodoDueDateUpdated(TodoDueDateUpdated::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoPriorityUpdated::DISCRIMINATOR {
+            TodoEvent::TodoPriorityUpdated(TodoPriorityUpdated::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoTagsUpdated::DISCRIMINATOR {
+            TodoEvent::TodoTagsUpdated(TodoTagsUpdated::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoDeleted::DISCRIMINATOR {
+            TodoEvent::TodoDeleted(TodoDeleted::deserialize(&mut payload).ok()?)
+        } else if discriminator == CompletedTodosDeleted::DISCRIMINATOR {
+            TodoEvent::CompletedTodosDeleted(CompletedTodosDeleted::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodosDeleted::DISCRIMINATOR {
+            TodoEvent::TodosDeleted(TodosDeleted::deserialize(&mut payload).ok()?)
+        } else if discriminator == UserClosed::DISCRIMINATOR {
+            TodoEvent::UserClosed(UserClosed::deserialize(&mut payload).ok()?)
+        } else if discriminator == DelegationGranted::DISCRIMINATOR {
+            TodoEvent::DelegationGranted(DelegationGranted::deserialize(&mut payload).ok()?)
+        } else if discriminator == DelegationUpdated::DISCRIMINATOR {
+            TodoEvent::DelegationUpdated(DelegationUpdated::deserialize(&mut payload).ok()?)
+        } else if discriminator == DelegationRevoked::DISCRIMINATOR {
+            TodoEvent::DelegationRevoked(DelegationRevoked::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoTransferOffered::DISCRIMINATOR {
+            TodoEvent::TodoTransferOffered(TodoTransferOffered::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoTransferred::DISCRIMINATOR {
+            TodoEvent::TodoTransferred(TodoTransferred::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoTransferCancelled::DISCRIMINATOR {
+            TodoEvent::TodoTransferCancelled(TodoTransferCancelled::deserialize(&mut payload).ok()?)
+        } else if discriminator == TodoMigrated::DISCRIMINATOR {
+            TodoEvent::TodoMigrated(TodoMigrated::deserialize(&mut payload).ok()?)
+        } else if discriminator == UserProfileMigrated::DISCRIMINATOR {
+            TodoEvent::UserProfileMigrated(UserProfileMigrated::deserialize(&mut payload).ok()?)
+        } else {
+            return None;
+        };
+
+        Some(event)
+    }
+}
+
+// Collect the events `program_id` emitted, in order, from a transaction's
+// log messages. Invocations are tracked so data logged by other programs,
+// including ones the todo program calls into, is ignored.
+pub fn decode_events(program_id: &Pubkey, logs: &[String]) -> Vec<TodoEvent> {
+    let program_id = program_id.to_string();
+    let mut invocations: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if invocations.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            if let Some(event) = general_purpose::STANDARD
+                .decode(data)
+                .ok()
+                .and_then(|bytes| TodoEvent::decode(&bytes))
+            {
+                events.push(event);
+            }
+            continue;
+        }
+
+        // "Program <id> invoke [<depth>]", "Program <id> success" and
+        // "Program <id> failed: <reason>" bracket each invocation
+        let mut words = log.split_whitespace();
+        if words.next() != Some("Program") {
+            continue;
+        }
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => invocations.push(id),
+            (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                invocations.pop();
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
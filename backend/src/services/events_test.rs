@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod tests {
+    use anchor_client::anchor_lang::Event;
+    use base64::{engine::general_purpose, Engine as _};
+    use solana_sdk::{
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use solana_todo::{
+        TodoCreated, TodoDeleted, TodoDescriptionUpdated, TodoStatus, TodoStatusChanged, UserInitialized,
+    };
+
+    use crate::{
+        models::todo::{CreateTodoRequest, UpdateTodoRequest},
+        services::{
+            chain::ChainClient,
+            events::{decode_events, TodoEvent},
+            in_memory::InMemoryChain,
+            todo::TodoService,
+        },
+    };
+
+    fn program_data<E: Event>(event: &E) -> String {
+        format!("Program data: {}", general_purpose::STANDARD.encode(event.data()))
+    }
+
+    async fn send(chain: &InMemoryChain, payer: &Keypair, instructions: &[Instruction]) -> Vec<TodoEvent> {
+        let blockhash = chain.latest_blockhash().await.unwrap().blockhash;
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+
+        let simulation = chain.simulate_transaction(&transaction).await.unwrap();
+        assert!(simulation.error.is_none());
+        chain.send_transaction(&transaction).await.unwrap();
+        decode_events(&solana_todo::ID, &simulation.logs)
+    }
+
+    #[actix_rt::test]
+    async fn test_decode_events_from_instruction_logs() {
+        let chain = std::sync::Arc::new(InMemoryChain::start(solana_todo::ID).await.unwrap());
+        let service = TodoService::new(chain.clone());
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+
+        let create = CreateTodoRequest {
+            description: "Write docs".to_string(),
+            due_date: 1625097600,
+        };
+        let events = send(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create),
+            ],
+        )
+        .await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], TodoEvent::UserInitialized(event) if event.authority == authority));
+        match &events[1] {
+            TodoEvent::TodoCreated(event) => {
+                assert_eq!(event.id, 1);
+                assert_eq!(event.owner, authority);
+                assert_eq!(event.description, "Write docs");
+                assert_eq!(event.due_date, 1625097600);
+                assert!(event.timestamp > 0);
+            }
+            other => panic!("expected TodoCreated, got {:?}", other),
+        }
+
+        let update = UpdateTodoRequest {
+            description: Some("Write better docs".to_string()),
+            status: None,
+            completed: Some(true),
+            due_date: Some(1625184000),
+            priority: None,
+            tags: None,
+        };
+        let events = send(&chain, &payer, &[service.update_todo_instruction(authority, authority, 1, &update).unwrap()]).await;
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            TodoEvent::TodoDescriptionUpdated(event)
+                if event.old_description == "Write docs" && event.new_description == "Write better docs"
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            TodoEvent::TodoStatusChanged(event)
+                if event.old_status == TodoStatus::Todo && event.new_status == TodoStatus::Done
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            TodoEvent::TodoDueDateUpdated(event)
+                if event.old_due_date == 1625097600 && event.new_due_date == 1625184000
+        )));
+
+        let labels = UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: None,
+            due_date: None,
+            priority: Some(3),
+            tags: Some(vec!["docs".to_string()]),
+        };
+        let events = send(&chain, &payer, &[service.update_todo_instruction(authority, authority, 1, &labels).unwrap()]).await;
+        match &events[..] {
+            [TodoEvent::TodoPriorityUpdated(priority), TodoEvent::TodoTagsUpdated(tags)] => {
+                assert_eq!((priority.old_priority, priority.new_priority), (0, 3));
+                assert!(tags.old_tags.is_empty());
+                assert_eq!(tags.new_tags, vec!["docs"]);
+            }
+            other => panic!("expected priority and tag events, got {:?}", other),
+        }
+
+        let events = send(&chain, &payer, &[service.delete_todo_instruction(authority, authority, 1)]).await;
+        assert!(matches!(&events[..], [TodoEvent::TodoDeleted(event)] if event.id == 1 && event.owner == authority));
+    }
+
+    #[test]
+    fn test_decode_events_ignores_other_programs() {
+        let program_id = solana_todo::ID.to_string();
+        let other_program = Pubkey::new_unique().to_string();
+        let owner = Pubkey::new_unique();
+        let deleted = TodoDeleted {
+            id: 7,
+            owner,
+            timestamp: 1700000000,
+        };
+        let status = TodoStatusChanged {
+            id: 7,
+            owner,
+            old_status: TodoStatus::InProgress,
+            new_status: TodoStatus::Blocked,
+            timestamp: 1700000000,
+        };
+
+        let logs = vec![
+            // Emitted by a different top-level program
+            format!("Program {} invoke [1]", other_program),
+            program_data(&deleted),
+            format!("Program {} success", other_program),
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Instruction: UpdateTodoStatus".to_string(),
+            // Emitted by a program the todo program called into
+            format!("Program {} invoke [2]", other_program),
+            program_data(&deleted),
+            format!("Program {} success", other_program),
+            // Not an event at all
+            "Program data: bm90IGFuIGV2ZW50".to_string(),
+            program_data(&status),
+            format!("Program {} success", program_id),
+        ];
+
+        assert_eq!(
+            decode_events(&solana_todo::ID, &logs),
+            vec![TodoEvent::TodoStatusChanged(status)]
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_events() {
+        let event = UserInitialized {
+            authority: Pubkey::new_unique(),
+            timestamp: 1700000000,
+        };
+        let data = event.data();
+
+        assert_eq!(TodoEvent::decode(&data), Some(TodoEvent::UserInitialized(event)));
+        assert_eq!(TodoEvent::decode(&data[..data.len() - 1]), None);
+        assert_eq!(TodoEvent::decode(&data[..4]), None);
+
+        let description = TodoDescriptionUpdated {
+            id: 1,
+            owner: Pubkey::new_unique(),
+            old_description: String::new(),
+            new_description: "New".to_string(),
+            timestamp: 0,
+        };
+        let created = TodoCreated {
+            id: 1,
+            owner: Pubkey::new_unique(),
+            description: "New".to_string(),
+            due_date: 0,
+            timestamp: 0,
+        };
+        assert_eq!(
+            TodoEvent::decode(&description.data()),
+            Some(TodoEvent::TodoDescriptionUpdated(description))
+        );
+        assert_eq!(TodoEvent::decode(&created.data()), Some(TodoEvent::TodoCreated(created)));
+    }
+}
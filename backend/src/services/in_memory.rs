@@ -0,0 +1,428 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Once;
+
+use anchor_client::solana_sdk::{
+    account::Account,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{self, SyscallStubs},
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use tokio::sync::Mutex;
+
+use crate::{
+    config::SolanaConfig,
+    error::AppError,
+    models::transaction::{ConfirmationLevel, TransactionStatus, TransactionStatusResponse},
+    services::{
+        chain::{CachedBlockhash, ChainClient, SimulationResult},
+        events::PROGRAM_DATA_PREFIX,
+        solana::SolanaService,
+    },
+};
+
+// Signers whose balance falls below this are topped back up to
+// SIGNER_FUNDING before their transaction runs
+const MIN_SIGNER_BALANCE: u64 = LAMPORTS_PER_SOL;
+const SIGNER_FUNDING: u64 = 10 * LAMPORTS_PER_SOL;
+
+// Anchor's entrypoint ties the account slice to the accounts' own lifetime,
+// which the processor! signature cannot express, so the slice's lifetime is
+// widened for the call rather than copied into storage that outlives it.
+fn process_instruction<'a, 'info>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    // SAFETY: the slice is owned by the caller and outlives this call, and
+    // the program keeps no reference to it once the instruction returns
+    let accounts: &'info [AccountInfo<'info>] = unsafe { &*(accounts as *const [AccountInfo<'info>]) };
+    solana_todo::entry(program_id, accounts, data)
+}
+
+// Prefix of the "Program log: " lines EventLogStubs writes for sol_log_data
+const EVENT_LOG_MARKER: &str = "in-memory-event: ";
+
+// solana-program-test prints sol_log_data to stdout when the program runs
+// natively, so Anchor events never reach the transaction logs. These stubs
+// wrap the ones ProgramTest installs and log the data through sol_log
+// instead; `restore_event_logs` turns those lines back into the
+// "Program data: " lines a validator writes.
+struct EventLogStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for EventLogStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let data: Vec<String> = fields.iter().map(|field| general_purpose::STANDARD.encode(field)).collect();
+        self.0.sol_log(&format!("{}{}", EVENT_LOG_MARKER, data.join(" ")))
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+// ProgramTest installs its stubs once, on the first start, so wrapping them
+// once afterwards covers every chain in the process
+fn install_event_log_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let stubs = program_stubs::set_syscall_stubs(Box::new(NoStubs));
+        program_stubs::set_syscall_stubs(Box::new(EventLogStubs(stubs)));
+    });
+}
+
+// Placeholder held only while the real stubs are being swapped out
+struct NoStubs;
+
+impl SyscallStubs for NoStubs {}
+
+fn restore_event_logs(logs: Vec<String>) -> Vec<String> {
+    let marker = format!("Program log: {}", EVENT_LOG_MARKER);
+    logs.into_iter()
+        .map(|log| match log.strip_prefix(&marker) {
+            Some(data) => format!("{}{}", PROGRAM_DATA_PREFIX, data),
+            None => log,
+        })
+        .collect()
+}
+
+// An in-process chain running the real todo program in a solana-program-test
+// bank, so every instruction executes the program's own code. Wallets need no
+// airdrop: signers are funded from the bank's faucet when they run low.
+// Transactions finalize as soon as they are processed.
+pub struct InMemoryChain {
+    program_id: Pubkey,
+    banks: Mutex<BanksClient>,
+    faucet: Keypair,
+    // BanksClient cannot enumerate program accounts, so every account a
+    // processed transaction referenced is remembered and scanned instead
+    touched_accounts: Mutex<HashSet<Pubkey>>,
+    statuses: Mutex<HashMap<Signature, TransactionStatusResponse>>,
+}
+
+impl InMemoryChain {
+    pub async fn start(program_id: Pubkey) -> Result<Self, AppError> {
+        Self::start_with_accounts(program_id, Vec::new()).await
+    }
+
+    // Start a chain with `accounts` already on it, running the program
+    // natively
+    pub async fn start_with_accounts(program_id: Pubkey, accounts: Vec<(Pubkey, Account)>) -> Result<Self, AppError> {
+        let mut program_test = ProgramTest::default();
+        program_test.prefer_bpf(false);
+        program_test.add_program("solana_todo", program_id, processor!(process_instruction));
+        Self::start_program_test(program_test, program_id, accounts).await
+    }
+
+    // Like start_with_accounts, running the compiled solana_todo.so that
+    // `cargo build-sbf` wrote to SBF_OUT_DIR, as a validator would
+    pub async fn start_sbf_with_accounts(
+        program_id: Pubkey,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<Self, AppError> {
+        let out_dir = env::var("SBF_OUT_DIR")
+            .map_err(|_| AppError::solana("SBF_OUT_DIR must point at the directory holding solana_todo.so"))?;
+        let program = Path::new(&out_dir).join("solana_todo.so");
+        if !program.is_file() {
+            return Err(AppError::solana(format!(
+                "{} not found; build the program with `cargo build-sbf`",
+                program.display()
+            )));
+        }
+
+        let mut program_test = ProgramTest::default();
+        program_test.prefer_bpf(true);
+        program_test.add_program("solana_todo", program_id, None);
+        Self::start_program_test(program_test, program_id, accounts).await
+    }
+
+    async fn start_program_test(
+        mut program_test: ProgramTest,
+        program_id: Pubkey,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Result<Self, AppError> {
+        // Anchor rejects instructions addressed to any id but the declared one
+        if program_id != solana_todo::ID {
+            return Err(AppError::solana(format!(
+                "The in-memory chain runs solana_todo at {}, not {}",
+                solana_todo::ID,
+                program_id
+            )));
+        }
+
+        let addresses: HashSet<Pubkey> = accounts.iter().map(|(address, _)| *address).collect();
+        for (address, account) in accounts {
+            program_test.add_account(address, account);
+        }
+        let (banks, faucet, _) = program_test.start().await;
+        install_event_log_stubs();
+
+        Ok(Self {
+            program_id,
+            banks: Mutex::new(banks),
+            faucet,
+            touched_accounts: Mutex::new(addresses),
+            statuses: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn from_config(config: &SolanaConfig) -> Result<Self, AppError> {
+        let program_id = Pubkey::from_str(&config.program_id)
+            .map_err(|e| AppError::solana(format!("Invalid program ID: {}", e)))?;
+
+        // A compiled program is used when one has been pointed at
+        if env::var_os("SBF_OUT_DIR").is_some() {
+            Self::start_sbf_with_accounts(program_id, Vec::new()).await
+        } else {
+            Self::start(program_id).await
+        }
+    }
+
+    // Top up the transaction's signers from the faucet, the way a local
+    // validator's airdrop would
+    async fn fund_signers(&self, banks: &mut BanksClient, transaction: &Transaction) -> Result<(), AppError> {
+        let message = &transaction.message;
+        let signers = &message.account_keys[..message.header.num_required_signatures as usize];
+
+        let mut transfers = Vec::new();
+        for signer in signers {
+            let balance = banks
+                .get_balance(*signer)
+                .await
+                .map_err(|e| AppError::solana(e.to_string()))?;
+            if balance < MIN_SIGNER_BALANCE {
+                transfers.push(system_instruction::transfer(
+                    &self.faucet.pubkey(),
+                    signer,
+                    SIGNER_FUNDING - balance,
+                ));
+            }
+        }
+        if transfers.is_empty() {
+            return Ok(());
+        }
+
+        let blockhash = banks
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| AppError::solana(e.to_string()))?;
+        let funding = Transaction::new_signed_with_payer(
+            &transfers,
+            Some(&self.faucet.pubkey()),
+            &[&self.faucet],
+            blockhash,
+        );
+        banks
+            .process_transaction(funding)
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to fund signers: {}", e)))
+    }
+
+    fn describe_failure(error: &TransactionError) -> String {
+        match SolanaService::decode_program_error(error) {
+            Some(program_error) => format!("{} ({}: {})", error, program_error.name, program_error.message),
+            None => error.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainClient for InMemoryChain {
+    fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    async fn get_account(&self, address: Pubkey) -> Result<Option<Account>, AppError> {
+        self.banks
+            .lock()
+            .await
+            .get_account(address)
+            .await
+            .map_err(|e| AppError::solana(e.to_string()))
+    }
+
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, AppError> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            accounts.push(self.get_account(*address).await?);
+        }
+        Ok(accounts)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        discriminator: [u8; 8],
+        filters: Vec<(usize, Vec<u8>)>,
+    ) -> Result<Vec<(Pubkey, Account)>, AppError> {
+        let addresses: Vec<Pubkey> = self.touched_accounts.lock().await.iter().copied().collect();
+
+        let mut accounts = Vec::new();
+        for address in addresses {
+            if let Some(account) = self.get_account(address).await? {
+                let matches = |offset: usize, bytes: &[u8]| account.data.get(offset..offset + bytes.len()) == Some(bytes);
+                if account.owner == self.program_id
+                    && matches(0, &discriminator)
+                    && filters.iter().all(|(offset, bytes)| matches(*offset, bytes))
+                {
+                    accounts.push((address, account));
+                }
+            }
+        }
+        Ok(accounts)
+    }
+
+    async fn latest_blockhash(&self) -> Result<CachedBlockhash, AppError> {
+        let mut banks = self.banks.lock().await;
+        let (blockhash, last_valid_block_height) = banks
+            .get_latest_blockhash_with_commitment(Default::default())
+            .await
+            .map_err(|e| AppError::solana(e.to_string()))?
+            .ok_or_else(|| AppError::solana("No blockhash available"))?;
+        let block_height = banks
+            .get_root_block_height()
+            .await
+            .map_err(|e| AppError::solana(e.to_string()))?;
+
+        Ok(CachedBlockhash {
+            blockhash,
+            last_valid_block_height,
+            block_height,
+            fetched_at: Utc::now(),
+        })
+    }
+
+    async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult, AppError> {
+        let mut banks = self.banks.lock().await;
+        self.fund_signers(&mut banks, transaction).await?;
+
+        let simulation = banks
+            .simulate_transaction(transaction.clone())
+            .await
+            .map_err(|e| AppError::solana(e.to_string()))?;
+        let details = simulation.simulation_details;
+
+        Ok(SimulationResult {
+            error: simulation.result.and_then(Result::err),
+            logs: restore_event_logs(details.as_ref().map(|details| details.logs.clone()).unwrap_or_default()),
+            units_consumed: details.map(|details| details.units_consumed),
+        })
+    }
+
+    async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, AppError> {
+        let signature = *transaction
+            .signatures
+            .first()
+            .ok_or_else(|| AppError::solana("Transaction has no signatures"))?;
+        if self.statuses.lock().await.contains_key(&signature) {
+            return Err(AppError::solana(format!(
+                "Failed to submit transaction: {}",
+                TransactionError::AlreadyProcessed
+            )));
+        }
+
+        let mut banks = self.banks.lock().await;
+        self.fund_signers(&mut banks, transaction).await?;
+
+        // Failures are reported the way RPC preflight does: the send itself
+        // errors and nothing is committed
+        let simulation = banks
+            .simulate_transaction(transaction.clone())
+            .await
+            .map_err(|e| AppError::solana(e.to_string()))?;
+        if let Some(Err(err)) = simulation.result {
+            return Err(AppError::solana(format!(
+                "Transaction simulation failed: {}",
+                Self::describe_failure(&err)
+            )));
+        }
+
+        banks
+            .process_transaction(transaction.clone())
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to submit transaction: {}", e)))?;
+        let slot = banks.get_root_slot().await.map_err(|e| AppError::solana(e.to_string()))?;
+
+        self.touched_accounts
+            .lock()
+            .await
+            .extend(transaction.message.account_keys.iter().copied());
+        self.statuses.lock().await.insert(
+            signature,
+            TransactionStatusResponse {
+                signature: signature.to_string(),
+                status: TransactionStatus::Finalized,
+                slot: Some(slot),
+                error: None,
+                program_error: None,
+            },
+        );
+
+        Ok(signature)
+    }
+
+    async fn get_transaction_status(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatusResponse>, AppError> {
+        Ok(self.statuses.lock().await.get(signature).cloned())
+    }
+
+    // Transactions finalize as soon as they are sent
+    async fn wait_for_confirmation(
+        &self,
+        signature: &Signature,
+        _level: ConfirmationLevel,
+    ) -> Result<TransactionStatusResponse, AppError> {
+        self.get_transaction_status(signature)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Transaction {} not found", signature)))
+    }
+}
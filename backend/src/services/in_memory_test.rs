@@ -0,0 +1,530 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anchor_lang::{error::ErrorCode as AnchorErrorCode, AccountDeserialize};
+    use solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_todo::{Delegation, TodoError, TodoItem, UserProfile};
+
+    use crate::{
+        models::todo::{CreateTodoRequest, TodoQuery, UpdateTodoRequest},
+        services::{
+            chain::ChainClient,
+            events::{decode_events, TodoEvent},
+            in_memory::InMemoryChain,
+            todo::TodoService,
+        },
+    };
+
+    async fn setup() -> (Arc<InMemoryChain>, TodoService) {
+        let chain = Arc::new(InMemoryChain::start(solana_todo::ID).await.unwrap());
+        let service = TodoService::new(chain.clone());
+        (chain, service)
+    }
+
+    fn create_request(description: &str) -> CreateTodoRequest {
+        CreateTodoRequest {
+            description: description.to_string(),
+            due_date: 1625097600,
+        }
+    }
+
+    async fn signed(chain: &InMemoryChain, payer: &Keypair, instructions: &[Instruction]) -> Transaction {
+        let blockhash = chain.latest_blockhash().await.unwrap().blockhash;
+        Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash)
+    }
+
+    // Error of the first failing instruction when simulating `instructions`
+    async fn simulated_error(chain: &InMemoryChain, payer: &Keypair, instructions: &[Instruction]) -> InstructionError {
+        let transaction = signed(chain, payer, instructions).await;
+        match chain.simulate_transaction(&transaction).await.unwrap().error {
+            Some(TransactionError::InstructionError(_, err)) => err,
+            other => panic!("expected an instruction error, got {:?}", other),
+        }
+    }
+
+    async fn todo(chain: &InMemoryChain, service: &TodoService, authority: Pubkey, id: u64) -> Option<TodoItem> {
+        let (address, _) = service.get_todo_pda(authority, id);
+        chain
+            .get_account(address)
+            .await
+            .unwrap()
+            .map(|account| TodoItem::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    async fn profile(service: &TodoService, authority: Pubkey) -> UserProfile {
+        service.get_user_profile(authority).await.unwrap().unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_create_todos_follow_the_profile_counter() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+
+        let first = signed(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create_request("First")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&first).await.unwrap();
+
+        let second = signed(
+            &chain,
+            &payer,
+            &[service.create_todo_instruction(authority, 2, &create_request("Second"))],
+        )
+        .await;
+        chain.send_transaction(&second).await.unwrap();
+
+        let profile = profile(&service, authority).await;
+        assert_eq!(profile.last_todo_id, 2);
+        assert_eq!(profile.todo_count, 2);
+
+        let todos = service.get_todos(&authority.to_string(), &TodoQuery::default()).await.unwrap();
+        assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(todos[1].description, "Second");
+
+        // Reusing an id already taken no longer matches the todo PDA seeds
+        let stale = service.create_todo_instruction(authority, 2, &create_request("Stale"));
+        assert_eq!(
+            simulated_error(&chain, &payer, &[stale]).await,
+            InstructionError::Custom(AnchorErrorCode::ConstraintSeeds.into())
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_create_todos_batch_continues_the_profile_counter() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+        let batch = [create_request("Second"), create_request("Third"), create_request("Fourth")];
+
+        let create = signed(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create_request("First")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&create).await.unwrap();
+
+        // The accounts have to match the ids the batch is assigned
+        assert_eq!(
+            simulated_error(&chain, &payer, &[service.create_todos_batch_instruction(authority, 3, &batch)]).await,
+            InstructionError::Custom(AnchorErrorCode::ConstraintSeeds.into())
+        );
+
+        let create = signed(&chain, &payer, &[service.create_todos_batch_instruction(authority, 2, &batch)]).await;
+        let simulation = chain.simulate_transaction(&create).await.unwrap();
+        let created: Vec<u64> = decode_events(&solana_todo::ID, &simulation.logs)
+            .iter()
+            .map(|event| match event {
+                TodoEvent::TodoCreated(event) => event.id,
+                other => panic!("expected TodoCreated, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(created, vec![2, 3, 4]);
+        chain.send_transaction(&create).await.unwrap();
+
+        assert_eq!(todo(&chain, &service, authority, 4).await.unwrap().description, "Fourth");
+        let profile = profile(&service, authority).await;
+        assert_eq!((profile.last_todo_id, profile.todo_count), (4, 4));
+    }
+
+    #[actix_rt::test]
+    async fn test_create_todo_enforces_program_limits() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+        let init = service.initialize_user_instruction(authority);
+
+        let too_long = service.create_todo_instruction(
+            authority,
+            1,
+            &create_request(&"é".repeat(TodoItem::MAX_DESCRIPTION_CHARS + 1)),
+        );
+        assert_eq!(
+            simulated_error(&chain, &payer, &[init.clone(), too_long]).await,
+            InstructionError::Custom(TodoError::DescriptionTooLong.into())
+        );
+
+        let past = CreateTodoRequest {
+            description: "Negative".to_string(),
+            due_date: -1,
+        };
+        let invalid_due = service.create_todo_instruction(authority, 1, &past);
+        assert_eq!(
+            simulated_error(&chain, &payer, &[init, invalid_due]).await,
+            InstructionError::Custom(TodoError::InvalidDueDate.into())
+        );
+
+        // A failed transaction leaves no trace, not even the profile
+        assert!(service.get_user_profile(authority).await.unwrap().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_todo_accounts_are_sized_to_their_description() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+        let (address, _) = service.get_todo_pda(authority, 1);
+        let space = || async { chain.get_account(address).await.unwrap().unwrap().data.len() };
+
+        let create = signed(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create_request("Short")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&create).await.unwrap();
+        assert_eq!(space().await, TodoItem::space("Short"));
+
+        let longest = "é".repeat(TodoItem::MAX_DESCRIPTION_CHARS);
+        let update = UpdateTodoRequest {
+            description: Some(longest.clone()),
+            status: None,
+            completed: None,
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+        let grow = signed(&chain, &payer, &[service.update_todo_instruction(authority, authority, 1, &update).unwrap()]).await;
+        chain.send_transaction(&grow).await.unwrap();
+        assert_eq!(space().await, TodoItem::space(&longest));
+
+        // Changing other fields keeps the size
+        let update = UpdateTodoRequest {
+            description: None,
+            priority: Some(1),
+            ..update
+        };
+        let keep = signed(&chain, &payer, &[service.update_todo_instruction(authority, authority, 1, &update).unwrap()]).await;
+        chain.send_transaction(&keep).await.unwrap();
+        assert_eq!(space().await, TodoItem::space(&longest));
+    }
+
+    #[actix_rt::test]
+    async fn test_only_the_owner_can_modify_a_todo() {
+        let (chain, service) = setup().await;
+        let owner = Keypair::new();
+        let intruder = Keypair::new();
+
+        let create = signed(
+            &chain,
+            &owner,
+            &[
+                service.initialize_user_instruction(owner.pubkey()),
+                service.create_todo_instruction(owner.pubkey(), 1, &create_request("Mine")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&create).await.unwrap();
+
+        // The intruder signs an update aimed at the owner's todo account
+        let (todo_account, _) = service.get_todo_pda(owner.pubkey(), 1);
+        let mut update = service
+            .update_todo_instruction(
+                intruder.pubkey(),
+                intruder.pubkey(),
+                1,
+                &UpdateTodoRequest {
+                    description: None,
+                    status: None,
+                    completed: Some(true),
+                    due_date: None,
+                    priority: None,
+                    tags: None,
+                },
+            )
+            .unwrap();
+        update.accounts[0].pubkey = todo_account;
+
+        // Naming themselves as the owner fails the todo's owner check, and
+        // naming the real owner still leaves them short of its authority
+        assert_eq!(
+            simulated_error(&chain, &intruder, &[update.clone()]).await,
+            InstructionError::Custom(TodoError::UnauthorizedAccess.into())
+        );
+        update.accounts[2].pubkey = owner.pubkey();
+        assert_eq!(
+            simulated_error(&chain, &intruder, &[update]).await,
+            InstructionError::Custom(AnchorErrorCode::ConstraintHasOne.into())
+        );
+        assert!(!todo(&chain, &service, owner.pubkey(), 1).await.unwrap().completed());
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_todo_closes_the_account() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+
+        let create = signed(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create_request("Done soon")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&create).await.unwrap();
+
+        let delete = signed(&chain, &payer, &[service.delete_todo_instruction(authority, authority, 1)]).await;
+        let signature = chain.send_transaction(&delete).await.unwrap();
+
+        assert!(todo(&chain, &service, authority, 1).await.is_none());
+        let profile = profile(&service, authority).await;
+        assert_eq!(profile.todo_count, 0);
+        assert_eq!(profile.last_todo_id, 1);
+
+        assert!(chain.get_transaction_status(&signature).await.unwrap().is_some());
+        // Replaying the same transaction is rejected
+        assert!(chain.send_transaction(&delete).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_completed_todos_only_takes_finished_todos() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+        let done = UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: Some(true),
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        let create = signed(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create_request("First")),
+                service.create_todo_instruction(authority, 2, &create_request("Second")),
+                service.create_todo_instruction(authority, 3, &create_request("Third")),
+                service.update_todo_instruction(authority, authority, 1, &done).unwrap(),
+                service.update_todo_instruction(authority, authority, 3, &done).unwrap(),
+            ],
+        )
+        .await;
+        chain.send_transaction(&create).await.unwrap();
+        let todos: Vec<Pubkey> = (1..=3).map(|id| service.get_todo_pda(authority, id).0).collect();
+
+        let open = service.delete_completed_todos_instruction(authority, &todos[..2]);
+        assert_eq!(
+            simulated_error(&chain, &payer, &[open]).await,
+            InstructionError::Custom(TodoError::TodoNotCompleted.into())
+        );
+
+        let completed = [todos[0], todos[2]];
+        let clear = signed(&chain, &payer, &[service.delete_completed_todos_instruction(authority, &completed)]).await;
+        let simulation = chain.simulate_transaction(&clear).await.unwrap();
+        match &decode_events(&solana_todo::ID, &simulation.logs)[..] {
+            [TodoEvent::CompletedTodosDeleted(event)] => assert_eq!(event.deleted_todo_ids, vec![1, 3]),
+            other => panic!("expected CompletedTodosDeleted, got {:?}", other),
+        }
+        chain.send_transaction(&clear).await.unwrap();
+
+        assert!(todo(&chain, &service, authority, 1).await.is_none());
+        assert!(todo(&chain, &service, authority, 2).await.is_some());
+        assert!(todo(&chain, &service, authority, 3).await.is_none());
+        assert_eq!(profile(&service, authority).await.todo_count, 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_close_user_closes_the_profile_with_its_todos() {
+        let (chain, service) = setup().await;
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+
+        let create = signed(
+            &chain,
+            &payer,
+            &[
+                service.initialize_user_instruction(authority),
+                service.create_todo_instruction(authority, 1, &create_request("First")),
+                service.create_todo_instruction(authority, 2, &create_request("Second")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&create).await.unwrap();
+        let todos = [service.get_todo_pda(authority, 1).0, service.get_todo_pda(authority, 2).0];
+
+        let partial = service.close_user_instruction(authority, &todos[..1]);
+        assert_eq!(
+            simulated_error(&chain, &payer, &[partial]).await,
+            InstructionError::Custom(TodoError::ProfileHasTodos.into())
+        );
+
+        let close = signed(&chain, &payer, &[service.close_user_instruction(authority, &todos)]).await;
+        let simulation = chain.simulate_transaction(&close).await.unwrap();
+        match &decode_events(&solana_todo::ID, &simulation.logs)[..] {
+            [TodoEvent::UserClosed(event)] => assert_eq!(event.closed_todo_ids, vec![1, 2]),
+            other => panic!("expected UserClosed, got {:?}", other),
+        }
+        chain.send_transaction(&close).await.unwrap();
+
+        assert!(service.get_user_profile(authority).await.unwrap().is_none());
+        for address in todos {
+            assert!(chain.get_account(address).await.unwrap().is_none());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_delegates_update_and_delete_within_their_permissions() {
+        let (chain, service) = setup().await;
+        let (owner, collaborator) = (Keypair::new(), Keypair::new());
+        let (owner_key, delegate) = (owner.pubkey(), collaborator.pubkey());
+        let status = UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: Some(true),
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+        let priority = UpdateTodoRequest {
+            completed: None,
+            priority: Some(3),
+            ..status.clone()
+        };
+
+        let setup = signed(
+            &chain,
+            &owner,
+            &[
+                service.initialize_user_instruction(owner_key),
+                service.create_todo_instruction(owner_key, 1, &create_request("Shared")),
+                service.create_todo_instruction(owner_key, 2, &create_request("Also shared")),
+                service.grant_delegation_instruction(owner_key, delegate, Delegation::SET_STATUS),
+            ],
+        )
+        .await;
+        let simulation = chain.simulate_transaction(&setup).await.unwrap();
+        match decode_events(&solana_todo::ID, &simulation.logs).last() {
+            Some(TodoEvent::DelegationGranted(event)) => {
+                assert_eq!((event.owner, event.delegate), (owner_key, delegate));
+                assert_eq!(event.permissions, Delegation::SET_STATUS);
+            }
+            other => panic!("expected DelegationGranted, got {:?}", other),
+        }
+        chain.send_transaction(&setup).await.unwrap();
+
+        let complete = service.update_todo_instruction(owner_key, delegate, 1, &status).unwrap();
+        let transaction = signed(&chain, &collaborator, &[complete]).await;
+        chain.send_transaction(&transaction).await.unwrap();
+        assert!(todo(&chain, &service, owner_key, 1).await.unwrap().completed());
+
+        // Priorities stay with the owner, and deleting was not granted
+        let reprioritize = service.update_todo_instruction(owner_key, delegate, 1, &priority).unwrap();
+        assert_eq!(
+            simulated_error(&chain, &collaborator, &[reprioritize]).await,
+            InstructionError::Custom(TodoError::MissingPermission.into())
+        );
+        let delete = service.delete_todo_instruction(owner_key, delegate, 2);
+        assert_eq!(
+            simulated_error(&chain, &collaborator, std::slice::from_ref(&delete)).await,
+            InstructionError::Custom(TodoError::MissingPermission.into())
+        );
+
+        let widen = signed(
+            &chain,
+            &owner,
+            &[service.update_delegation_instruction(owner_key, delegate, Delegation::ALL_PERMISSIONS)],
+        )
+        .await;
+        chain.send_transaction(&widen).await.unwrap();
+        let transaction = signed(&chain, &collaborator, &[delete]).await;
+        chain.send_transaction(&transaction).await.unwrap();
+        assert!(todo(&chain, &service, owner_key, 2).await.is_none());
+        assert_eq!(profile(&service, owner_key).await.todo_count, 1);
+
+        // Once revoked, the collaborator is back to being a stranger
+        let revoke = signed(&chain, &owner, &[service.revoke_delegation_instruction(owner_key, delegate)]).await;
+        chain.send_transaction(&revoke).await.unwrap();
+        assert!(service.get_delegation(owner_key, delegate).await.unwrap().is_none());
+        let complete = service.update_todo_instruction(owner_key, delegate, 1, &status).unwrap();
+        assert_eq!(
+            simulated_error(&chain, &collaborator, &[complete]).await,
+            InstructionError::Custom(AnchorErrorCode::AccountNotInitialized.into())
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_transfer_todo_moves_it_to_the_recipients_next_id() {
+        let (chain, service) = setup().await;
+        let (owner, receiver) = (Keypair::new(), Keypair::new());
+        let (owner_key, recipient) = (owner.pubkey(), receiver.pubkey());
+
+        let setup = signed(
+            &chain,
+            &owner,
+            &[
+                service.initialize_user_instruction(owner_key),
+                service.create_todo_instruction(owner_key, 1, &create_request("Hand over")),
+                service.offer_transfer_instruction(owner_key, 1, recipient),
+            ],
+        )
+        .await;
+        chain.send_transaction(&setup).await.unwrap();
+        let transaction = signed(
+            &chain,
+            &receiver,
+            &[
+                service.initialize_user_instruction(recipient),
+                service.create_todo_instruction(recipient, 1, &create_request("Mine")),
+            ],
+        )
+        .await;
+        chain.send_transaction(&transaction).await.unwrap();
+
+        // Nobody but the recipient can take the todo
+        let intruder = Keypair::new();
+        let transaction = signed(&chain, &intruder, &[service.initialize_user_instruction(intruder.pubkey())]).await;
+        chain.send_transaction(&transaction).await.unwrap();
+        let steal = service.transfer_todo_instruction(owner_key, 1, intruder.pubkey(), 1);
+        assert_eq!(
+            simulated_error(&chain, &intruder, &[steal]).await,
+            InstructionError::Custom(TodoError::NotTransferRecipient.into())
+        );
+
+        let accept = signed(&chain, &receiver, &[service.transfer_todo_instruction(owner_key, 1, recipient, 2)]).await;
+        let simulation = chain.simulate_transaction(&accept).await.unwrap();
+        match decode_events(&solana_todo::ID, &simulation.logs).last() {
+            Some(TodoEvent::TodoTransferred(event)) => {
+                assert_eq!((event.from, event.to), (owner_key, recipient));
+                assert_eq!((event.old_id, event.new_id), (1, 2));
+            }
+            other => panic!("expected TodoTransferred, got {:?}", other),
+        }
+        chain.send_transaction(&accept).await.unwrap();
+
+        let moved = todo(&chain, &service, recipient, 2).await.unwrap();
+        assert_eq!(moved.description, "Hand over");
+        assert_eq!((moved.owner, moved.authority), (recipient, recipient));
+        assert!(todo(&chain, &service, owner_key, 1).await.is_none());
+        let (todo_account, _) = service.get_todo_pda(owner_key, 1);
+        assert!(service.get_transfer_offer(todo_account).await.unwrap().is_none());
+        assert_eq!(profile(&service, owner_key).await.todo_count, 0);
+        let recipient_profile = profile(&service, recipient).await;
+        assert_eq!((recipient_profile.todo_count, recipient_profile.last_todo_id), (2, 2));
+    }
+}
@@ -1,7 +1,24 @@
 pub mod auth;
+pub mod chain;
+pub mod events;
+#[cfg(feature = "in-memory")]
+pub mod in_memory;
 pub mod solana;
 pub mod todo;
 
+#[cfg(all(test, feature = "in-memory"))]
+mod events_test;
+#[cfg(all(test, feature = "in-memory"))]
+mod in_memory_test;
+#[cfg(test)]
+mod solana_test;
+#[cfg(test)]
+mod todo_test;
+
 pub use auth::*;
+pub use chain::*;
+pub use events::*;
+#[cfg(feature = "in-memory")]
+pub use in_memory::*;
 pub use solana::*;
-pub use todo::*; 
\ No newline at end of file
+pub use todo::*;
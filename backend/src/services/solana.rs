@@ -1,572 +1,437 @@
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
-use solana_client::nonblocking::rpc_client;
-//  use anchor_lang::prelude::AccountMeta;
- 
-  use solana_program::instruction::AccountMeta;
- 
-//   use solana_sdk::instruction::AccountMeta;
-use actix_web::web;
-use anchor_client::{
-    anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, InstructionData, ToAccountMetas},
-    solana_sdk::{
-        commitment_config::CommitmentConfig,
-        instruction::Instruction,
-        message::Message,
-        pubkey::Pubkey,
-        signature::{Keypair, Signature},
-        signer::Signer,
-        transaction::Transaction,
-    },
-    Client, Cluster, Program,
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anchor_client::solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::warn;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
 };
-use anyhow::Result;
-use base64::{engine::general_purpose, Engine as _};
-use log::{error, info};
-use serde_json::Value;
-use solana_client::rpc_client::RpcClient;
-use solana_program::{instruction::Instruction as SolanaInstruction, system_program};
-use solana_sdk::msg;
-use solana_sdk::{
-    signature::Signature as SolanaSignature, 
-    signer::keypair::Keypair as SolanaKeypair,
+use solana_todo::TodoError;
+use solana_transaction_status::{
+    TransactionConfirmationStatus, TransactionStatus as RpcTransactionStatus,
 };
 
 use crate::{
-    config::get_config,
+    config::{get_config, SolanaConfig},
     error::AppError,
-    models::{
-        todo::{CreateTodoRequest, Todo, UpdateTodoRequest},
-        transaction::{PreparedTransaction, SignedTransaction},
+    models::transaction::{
+        ConfirmationLevel, ProgramErrorDetails, TransactionStatus, TransactionStatusResponse,
     },
+    services::chain::{CachedBlockhash, ChainClient, SimulationResult},
 };
 
+// How often the background task refreshes the blockhash
+const BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// How often submitted transactions are polled, how long a caller may block on
+// wait_for, and how long settled transactions stay queryable from memory
+const TRACKER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const WAIT_FOR_TIMEOUT: Duration = Duration::from_secs(90);
+const TRACKED_RETENTION_SECS: i64 = 600;
+
+#[derive(Clone, Debug)]
+struct TrackedTransaction {
+    status: TransactionStatusResponse,
+    blockhash: Hash,
+    updated_at: DateTime<Utc>,
+}
+
 #[derive(Clone)]
 pub struct SolanaService {
-    rpc_url: String,
+    rpc_client: Arc<RpcClient>,
     program_id: Pubkey,
+    blockhash_cache: Arc<RwLock<Option<CachedBlockhash>>>,
+    tracked_transactions: Arc<RwLock<HashMap<Signature, TrackedTransaction>>>,
 }
 
 impl SolanaService {
     pub fn new() -> Result<Self, AppError> {
-        let config = get_config();
-        let rpc_url = config.solana.rpc_url.clone();
-        let program_id = Pubkey::from_str(&config.solana.program_id)
+        Self::from_config(&get_config().solana)
+    }
+
+    pub fn from_config(config: &SolanaConfig) -> Result<Self, AppError> {
+        let program_id = Pubkey::from_str(&config.program_id)
             .map_err(|e| AppError::solana(format!("Invalid program ID: {}", e)))?;
+        let commitment = CommitmentConfig::from_str(&config.commitment)
+            .map_err(|e| AppError::solana(format!("Invalid commitment level: {}", e)))?;
+
+        // One client for the whole service; it pools connections internally
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), commitment);
 
         Ok(Self {
-            rpc_url,
+            rpc_client: Arc::new(rpc_client),
             program_id,
+            blockhash_cache: Arc::new(RwLock::new(None)),
+            tracked_transactions: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
-    fn create_rpc_client(&self) -> RpcClient {
-        RpcClient::new(self.rpc_url.clone())
+    // Keep the blockhash cache warm so prepare calls rarely hit the RPC node
+    pub fn start_blockhash_refresh(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(BLOCKHASH_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.refresh_blockhash().await {
+                    warn!("Failed to refresh blockhash: {}", e);
+                }
+            }
+        });
     }
 
-    // Verify a signature from a Solana wallet
-    pub fn verify_signature(
-        &self,
-        public_key: &str,
-        message: &str,
-        signature: &str,
-    ) -> Result<bool, AppError> {
-        // Convert public key string to Pubkey
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-
-        // Convert signature string to Signature
-        let signature_bytes = bs58::decode(signature)
-            .into_vec()
-            .map_err(|e| AppError::bad_request(format!("Invalid signature format: {}", e)))?;
-
-        if signature_bytes.len() != 64 {
-            return Err(AppError::bad_request("Invalid signature length"));
-        }
-
-        let signature = SolanaSignature::try_from(signature_bytes.as_slice())
-            .map_err(|e| AppError::bad_request(format!("Invalid signature: {}", e)))?;
-
-        // Convert message to bytes
-        let message_bytes = message.as_bytes();
+    async fn refresh_blockhash(&self) -> Result<CachedBlockhash, AppError> {
+        let (blockhash, last_valid_block_height) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(self.rpc_client.commitment())
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to get recent blockhash: {}", e)))?;
+        let block_height = self
+            .rpc_client
+            .get_block_height()
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to get block height: {}", e)))?;
+
+        let cached = CachedBlockhash {
+            blockhash,
+            last_valid_block_height,
+            block_height,
+            fetched_at: Utc::now(),
+        };
 
-        // Verify the signature
-        let is_valid = signature.verify(pubkey.as_ref(), message_bytes);
+        *self
+            .blockhash_cache
+            .write()
+            .map_err(|_| AppError::internal("Blockhash cache lock poisoned"))? = Some(cached);
 
-        Ok(is_valid)
+        Ok(cached)
     }
 
-    // Get the PDAs for user profile and todo items
-    fn get_user_profile_pda(&self, authority: Pubkey) -> (Pubkey, u8) {
-        Pubkey::find_program_address(
-            &[b"user_profile", authority.as_ref()],
-            &self.program_id,
-        )
+    // Poll every pending submitted transaction until it settles
+    pub fn start_transaction_tracker(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TRACKER_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.poll_tracked_transactions(None).await {
+                    warn!("Failed to poll transaction statuses: {}", e);
+                }
+            }
+        });
     }
 
-    fn get_todo_pda(&self, authority: Pubkey, todo_id: u64) -> (Pubkey, u8) {
-        Pubkey::find_program_address(
-            &[
-                b"todo",
-                authority.as_ref(),
-                &todo_id.to_le_bytes(),
-            ],
-            &self.program_id,
-        )
+    fn tracked_status(&self, signature: &Signature) -> Result<Option<TransactionStatusResponse>, AppError> {
+        Ok(self
+            .tracked_transactions
+            .read()
+            .map_err(|_| AppError::internal("Transaction tracker lock poisoned"))?
+            .get(signature)
+            .map(|tracked| tracked.status.clone()))
     }
 
-    // Get all todos for a user
-    pub async fn get_todos(&self, public_key: &str) -> Result<Vec<Todo>, AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-
-        // In a real implementation, we would use the Anchor client to fetch all todos
-        // Here we'll simulate the response for demonstration purposes
-        
-        // Mock response for demonstration
-        let mock_todos = vec![
-            Todo {
-                id: 1,
-                description: "Complete Solana program".to_string(),
-                completed: false,
-                due_date: 1625097600,
-                owner: public_key.to_string(),
-            },
-            Todo {
-                id: 2,
-                description: "Implement frontend".to_string(),
-                completed: true,
-                due_date: 1625184000,
-                owner: public_key.to_string(),
-            },
-        ];
+    // Refresh pending tracked transactions (or just `only`) and drop long-settled ones
+    async fn poll_tracked_transactions(&self, only: Option<Signature>) -> Result<(), AppError> {
+        let pending: Vec<(Signature, Hash)> = {
+            let mut tracked = self
+                .tracked_transactions
+                .write()
+                .map_err(|_| AppError::internal("Transaction tracker lock poisoned"))?;
+            let now = Utc::now();
+            tracked.retain(|_, entry| {
+                !entry.status.status.is_terminal()
+                    || now - entry.updated_at < ChronoDuration::seconds(TRACKED_RETENTION_SECS)
+            });
+            tracked
+                .iter()
+                .filter(|(signature, entry)| {
+                    !entry.status.status.is_terminal() && only.is_none_or(|only| only == **signature)
+                })
+                .map(|(signature, entry)| (*signature, entry.blockhash))
+                .collect()
+        };
 
-        Ok(mock_todos)
-    }
+        if pending.is_empty() {
+            return Ok(());
+        }
 
-    // Create a new todo
-    pub async fn create_todo(
-        &self,
-        public_key: &str,
-        todo: CreateTodoRequest,
-    ) -> Result<Todo, AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-
-        // Validate request
-        if todo.description.is_empty() {
-            return Err(AppError::bad_request("Description cannot be empty"));
+        let mut updates = Vec::with_capacity(pending.len());
+        // Transactions prepared together share a blockhash, so each distinct
+        // one is checked at most once per poll
+        let mut blockhash_validity: HashMap<Hash, bool> = HashMap::new();
+
+        // The RPC node accepts at most 256 signatures per status query
+        for chunk in pending.chunks(256) {
+            let signatures: Vec<Signature> = chunk.iter().map(|(signature, _)| *signature).collect();
+            let statuses = self
+                .rpc_client
+                .get_signature_statuses(&signatures)
+                .await
+                .map_err(|e| AppError::solana(format!("Failed to get signature statuses: {}", e)))?
+                .value;
+
+            for ((signature, blockhash), status) in chunk.iter().zip(statuses) {
+                let update = match status {
+                    Some(status) => Some(Self::status_response(signature, &status)),
+                    // Not seen yet: once the blockhash expires it never will be
+                    None => {
+                        let valid = match blockhash_validity.get(blockhash) {
+                            Some(valid) => *valid,
+                            None => {
+                                let valid = self
+                                    .rpc_client
+                                    .is_blockhash_valid(blockhash, CommitmentConfig::processed())
+                                    .await
+                                    .map_err(|e| AppError::solana(format!("Failed to check blockhash: {}", e)))?;
+                                blockhash_validity.insert(*blockhash, valid);
+                                valid
+                            }
+                        };
+                        (!valid).then(|| TransactionStatusResponse {
+                            signature: signature.to_string(),
+                            status: TransactionStatus::Expired,
+                            slot: None,
+                            error: None,
+                            program_error: None,
+                        })
+                    }
+                };
+
+                if let Some(update) = update {
+                    updates.push((*signature, update));
+                }
+            }
         }
 
-        if todo.description.len() > 280 {
-            return Err(AppError::bad_request("Description must be 280 characters or less"));
+        let mut tracked = self
+            .tracked_transactions
+            .write()
+            .map_err(|_| AppError::internal("Transaction tracker lock poisoned"))?;
+        for (signature, status) in updates {
+            if let Some(entry) = tracked.get_mut(&signature) {
+                entry.status = status;
+                entry.updated_at = Utc::now();
+            }
         }
 
-        if todo.due_date < 0 {
-            return Err(AppError::bad_request("Due date must be a valid timestamp"));
+        Ok(())
+    }
+
+    // Convert an RPC signature status into the API representation
+    pub(crate) fn status_response(signature: &Signature, status: &RpcTransactionStatus) -> TransactionStatusResponse {
+        let (state, error, program_error) = match &status.err {
+            Some(err) => (
+                TransactionStatus::Failed,
+                Some(err.to_string()),
+                Self::decode_program_error(err),
+            ),
+            None => {
+                let state = match status.confirmation_status {
+                    Some(TransactionConfirmationStatus::Finalized) => TransactionStatus::Finalized,
+                    Some(TransactionConfirmationStatus::Confirmed) => TransactionStatus::Confirmed,
+                    _ => TransactionStatus::Processed,
+                };
+                (state, None, None)
+            }
+        };
+
+        TransactionStatusResponse {
+            signature: signature.to_string(),
+            status: state,
+            slot: Some(status.slot),
+            error,
+            program_error,
         }
+    }
 
-        // In a real implementation, we would use the Anchor client to send a transaction
-        // Here we'll simulate the response for demonstration purposes
-        
-        // Mock response for demonstration
-        let new_todo = Todo {
-            id: 3, // In a real implementation, this would be the next available ID
-            description: todo.description,
-            completed: false,
-            due_date: todo.due_date,
-            owner: public_key.to_string(),
+    // Map a custom instruction error back to the program's TodoError
+    pub(crate) fn decode_program_error(error: &TransactionError) -> Option<ProgramErrorDetails> {
+        let TransactionError::InstructionError(_, InstructionError::Custom(code)) = error else {
+            return None;
         };
 
-        Ok(new_todo)
+        TodoError::ALL
+            .iter()
+            .find(|todo_error| u32::from(**todo_error) == *code)
+            .map(|todo_error| ProgramErrorDetails {
+                code: *code,
+                name: todo_error.name(),
+                message: todo_error.to_string(),
+            })
     }
+}
 
-    // Update a todo
-    pub async fn update_todo(
-        &self,
-        public_key: &str,
-        todo_id: u64,
-        update: UpdateTodoRequest,
-    ) -> Result<Todo, AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-
-        // Validate request
-        if let Some(ref description) = update.description {
-            if description.is_empty() {
-                return Err(AppError::bad_request("Description cannot be empty"));
-            }
+#[async_trait]
+impl ChainClient for SolanaService {
+    fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
 
-            if description.len() > 280 {
-                return Err(AppError::bad_request("Description must be 280 characters or less"));
-            }
+    async fn get_account(&self, address: Pubkey) -> Result<Option<Account>, AppError> {
+        Ok(self
+            .rpc_client
+            .get_account_with_commitment(&address, self.rpc_client.commitment())
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to fetch account {}: {}", address, e)))?
+            .value)
+    }
+
+    async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, AppError> {
+        // RPC nodes serve at most MAX_MULTIPLE_ACCOUNTS addresses per request
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let fetched = self
+                .rpc_client
+                .get_multiple_accounts_with_commitment(chunk, self.rpc_client.commitment())
+                .await
+                .map_err(|e| AppError::solana(format!("Failed to fetch accounts: {}", e)))?
+                .value;
+            accounts.extend(fetched);
         }
+        Ok(accounts)
+    }
 
-        // In a real implementation, we would use the Anchor client to send a transaction
-        // Here we'll simulate the response for demonstration purposes
-        
-        // Mock response for demonstration
-        let updated_todo = Todo {
-            id: todo_id,
-            description: update.description.unwrap_or_else(|| "Original description".to_string()),
-            completed: update.completed.unwrap_or(false),
-            due_date: 1625097600,
-            owner: public_key.to_string(),
+    async fn get_program_accounts(
+        &self,
+        discriminator: [u8; 8],
+        filters: Vec<(usize, Vec<u8>)>,
+    ) -> Result<Vec<(Pubkey, Account)>, AppError> {
+        let filters = [(0, discriminator.to_vec())]
+            .into_iter()
+            .chain(filters)
+            .map(|(offset, bytes)| RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, &bytes)))
+            .collect();
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.rpc_client.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
         };
 
-        Ok(updated_todo)
+        self.rpc_client
+            .get_program_accounts_with_config(&self.program_id, config)
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to fetch program accounts: {}", e)))
     }
 
-    // Delete a todo
-    pub async fn delete_todo(&self, public_key: &str, todo_id: u64) -> Result<(), AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
+    // Return the cached blockhash, fetching a new one if the cache is cold or stale
+    async fn latest_blockhash(&self) -> Result<CachedBlockhash, AppError> {
+        let cached = *self
+            .blockhash_cache
+            .read()
+            .map_err(|_| AppError::internal("Blockhash cache lock poisoned"))?;
 
-        // In a real implementation, we would use the Anchor client to send a transaction
-        // Here we'll simulate the response for demonstration purposes
-        
-        Ok(())
+        match cached {
+            Some(cached) if cached.is_fresh(Utc::now()) => Ok(cached),
+            _ => self.refresh_blockhash().await,
+        }
     }
-    pub async fn prepare_create_todo_transaction(
-        &self,
-        public_key: &str,
-        todo: CreateTodoRequest,
-    ) -> Result<PreparedTransaction, AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-            
-        // Clone values needed for the blocking operation
-        let program_id = self.program_id;
-        let rpc_url = self.rpc_url.clone();
-        
-        // Use web::block or tokio::task::spawn_blocking to run RPC operations
-        let result = web::block(move || {
-            // Create a new RPC client for this request
-            let rpc_client = RpcClient::new(rpc_url);
-            
-            // Get the latest blockhash - this is a blocking operation
-            let recent_blockhash = rpc_client.get_latest_blockhash()
-                .map_err(|e| AppError::solana(format!("Failed to get recent blockhash: {}", e)))?;
-                
-            // 1. Get the next todo ID from the user profile
-            let next_todo_id = 1; // This would be fetched from the user profile PDA
-            
-            // 2. Get the PDAs for the todo and user profile
-            let (todo_pda, _) = Pubkey::find_program_address(
-                &[b"todo", pubkey.as_ref(), &(next_todo_id as u64).to_le_bytes()],
-                &program_id,
-            );
-            let (user_profile_pda, _) = Pubkey::find_program_address(
-                &[b"user_profile", pubkey.as_ref()],
-                &program_id,
-            );
-            
-            // 3. Build the instruction for creating a todo
-            let instruction = Instruction {
-                program_id,
-                accounts: vec![
-                    solana_sdk::instruction::AccountMeta::new(todo_pda, false),
-                    solana_sdk::instruction::AccountMeta::new(user_profile_pda, false),
-                    solana_sdk::instruction::AccountMeta::new(pubkey, true),
-                    solana_sdk::instruction::AccountMeta::new_readonly(system_program::id(), false),
-                ],
-                data: vec![0], // Placeholder for instruction data
-            };
-            
-            // Create the transaction
-            let transaction = Transaction::new_unsigned(Message::new(
-                &[instruction],
-                Some(&pubkey),
-            ));
-            
-            // Serialize the transaction
-            let serialized_transaction = general_purpose::STANDARD.encode(
-                bincode::serialize(&transaction)
-                    .map_err(|e| AppError::internal(format!("Failed to serialize transaction: {}", e)))?
-            );
-            
-            // Return the prepared transaction
-            Ok::<PreparedTransaction, AppError>(PreparedTransaction {
-                serialized_transaction,
-                transaction_type: "create_todo".to_string(),
-                metadata: Some(serde_json::to_string(&todo)
-                    .map_err(|e| AppError::internal(format!("Failed to serialize metadata: {}", e)))?),
-            })
-        })
-        .await
-        .map_err(|e| AppError::internal(format!("Task execution error: {}", e)))?;
 
-        result
+    async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult, AppError> {
+        let result = self
+            .rpc_client
+            .simulate_transaction(transaction)
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to simulate transaction: {}", e)))?
+            .value;
+
+        Ok(SimulationResult {
+            error: result.err,
+            logs: result.logs.unwrap_or_default(),
+            units_consumed: result.units_consumed,
+        })
     }
-    // Prepare a transaction for updating a todo
-    // pub async fn prepare_update_todo_transaction(
-    //     &self,
-    //     public_key: &str,
-    //     todo_id: u64,
-    //     update: UpdateTodoRequest,
-    // ) -> Result<PreparedTransaction, AppError> {
-    //     let pubkey = Pubkey::from_str(public_key)
-    //         .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-            
-    //     // Create a new RPC client for this request
-    //     let rpc_client = self.create_rpc_client();
-        
-    //     // Get the todo PDA
-    //     let (todo_pda, _) = self.get_todo_pda(pubkey, todo_id);
-        
-    //     // Build the instruction
-    //     let instruction = Instruction {
-    //         program_id: self.program_id,
-    //         accounts: vec![
-    //             solana_sdk::instruction::AccountMeta::new(todo_pda, false),
-    //             solana_sdk::instruction::AccountMeta::new(pubkey, true),
-    //         ],
-    //         data: vec![1], // Placeholder for instruction data
-    //     };
-        
-    //     // Get the latest blockhash
-    //     let recent_blockhash = rpc_client
-    //         .get_latest_blockhash()
-    //         .map_err(|e| AppError::solana(format!("Failed to get recent blockhash: {}", e)))?;
-            
-    //     // Create the transaction
-    //     let transaction = Transaction::new_unsigned(Message::new(
-    //         &[instruction],
-    //         Some(&pubkey),
-    //     ));
-        
-    //     // Serialize the transaction
-    //     let serialized_transaction = general_purpose::STANDARD.encode(bincode::serialize(&transaction)
-    //         .map_err(|e| AppError::internal(format!("Failed to serialize transaction: {}", e)))?);
-            
-    //     // Return the prepared transaction
-    //     Ok(PreparedTransaction {
-    //         serialized_transaction,
-    //         transaction_type: "update_todo".to_string(),
-    //         metadata: Some(serde_json::to_string(&update)
-    //             .map_err(|e| AppError::internal(format!("Failed to serialize metadata: {}", e)))?),
-    //     })
-    // }
-    
-    // // Prepare a transaction for deleting a todo
-    // pub async fn prepare_delete_todo_transaction(
-    //     &self,
-    //     public_key: &str,
-    //     todo_id: u64,
-    // ) -> Result<PreparedTransaction, AppError> {
-    //     let pubkey = Pubkey::from_str(public_key)
-    //         .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-            
-    //     // Create a new RPC client for this request
-    //     let rpc_client = self.create_rpc_client();
-        
-    //     // Get the PDAs
-    //     let (todo_pda, _) = self.get_todo_pda(pubkey, todo_id);
-    //     let (user_profile_pda, _) = self.get_user_profile_pda(pubkey);
-        
-    //     // Build the instruction
-    //     let instruction = Instruction {
-    //         program_id: self.program_id,
-    //         accounts: vec![
-    //             solana_sdk::instruction::AccountMeta::new(user_profile_pda, false),
-    //             solana_sdk::instruction::AccountMeta::new(todo_pda, false),
-    //             solana_sdk::instruction::AccountMeta::new(pubkey, true),
-    //         ],
-    //         data: vec![2], // Placeholder for instruction data
-    //     };
-        
-    //     // Get the latest blockhash
-    //     let recent_blockhash = rpc_client
-    //         .get_latest_blockhash()
-    //         .map_err(|e| AppError::solana(format!("Failed to get recent blockhash: {}", e)))?;
-            
-    //     // Create the transaction
-    //     let transaction = Transaction::new_unsigned(Message::new(
-    //         &[instruction],
-    //         Some(&pubkey),
-    //     ));
-        
-    //     // Serialize the transaction
-    //     let serialized_transaction = general_purpose::STANDARD.encode(bincode::serialize(&transaction)
-    //         .map_err(|e| AppError::internal(format!("Failed to serialize transaction: {}", e)))?);
-            
-    //     // Return the prepared transaction
-    //     Ok(PreparedTransaction {
-    //         serialized_transaction,
-    //         transaction_type: "delete_todo".to_string(),
-    //         metadata: Some(serde_json::to_string(&todo_id)
-    //             .map_err(|e| AppError::internal(format!("Failed to serialize metadata: {}", e)))?),
-    //     })
-    // }
-    
-
-    pub async fn prepare_update_todo_transaction(
-        &self,
-        public_key: &str,
-        todo_id: u64,
-        update: UpdateTodoRequest,
-    ) -> Result<PreparedTransaction, AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-            
-        // Clone values needed for the blocking operation
-        let program_id = self.program_id;
-        let rpc_url = self.rpc_url.clone();
-        
-        // Use web::block for RPC operations
-        let result = web::block(move || {
-            // Create a new RPC client for this request
-            let rpc_client = RpcClient::new(rpc_url);
-            
-            // Get the latest blockhash
-            let recent_blockhash = rpc_client.get_latest_blockhash()
-                .map_err(|e| AppError::solana(format!("Failed to get recent blockhash: {}", e)))?;
-            
-            // Get the todo PDA
-            let (todo_pda, _) = Pubkey::find_program_address(
-                &[b"todo", pubkey.as_ref(), &todo_id.to_le_bytes()],
-                &program_id,
-            );
-            
-            // Build the instruction
-            let mut instruction_data = vec![1]; // 1 = update instruction
-            
-            if let Some(completed) = update.completed {
-                instruction_data.push(if completed { 1 } else { 0 });
-            }
-            
-            if let Some(description) = &update.description {
-                instruction_data.extend_from_slice(description.as_bytes());
-            }
-            
-            let instruction = Instruction {
-                program_id,
-                accounts: vec![
-                    AccountMeta::new(todo_pda, false),
-                    AccountMeta::new(pubkey, true),
-                ],
-                data: instruction_data,
-            };
-            
-            // Create the transaction
-            let message = Message::new(&[instruction], Some(&pubkey));
-            let transaction = Transaction::new_unsigned(message);
-            
-            // Serialize the transaction
-            let serialized_transaction = general_purpose::STANDARD.encode(
-                bincode::serialize(&transaction)
-                    .map_err(|e| AppError::internal(format!("Failed to serialize transaction: {}", e)))?
+
+    async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, AppError> {
+        let signature = self
+            .rpc_client
+            .send_transaction(transaction)
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to submit transaction: {}", e)))?;
+
+        self.tracked_transactions
+            .write()
+            .map_err(|_| AppError::internal("Transaction tracker lock poisoned"))?
+            .insert(
+                signature,
+                TrackedTransaction {
+                    status: TransactionStatusResponse {
+                        signature: signature.to_string(),
+                        status: TransactionStatus::Pending,
+                        slot: None,
+                        error: None,
+                        program_error: None,
+                    },
+                    blockhash: transaction.message.recent_blockhash,
+                    updated_at: Utc::now(),
+                },
             );
-            
-            Ok::<PreparedTransaction, AppError>(PreparedTransaction {
-                serialized_transaction,
-                transaction_type: "update_todo".to_string(),
-                metadata: Some(serde_json::to_string(&update)
-                    .map_err(|e| AppError::internal(format!("Failed to serialize metadata: {}", e)))?),
-            })
-        })
-        .await
-        .map_err(|e| AppError::internal(format!("Task execution error: {}", e)))??;
-        
-        Ok(result)
+
+        Ok(signature)
     }
-    
-    pub async fn prepare_delete_todo_transaction(
+
+    // Status of a transaction, from the tracker if it was submitted here or from the node otherwise
+    async fn get_transaction_status(
         &self,
-        public_key: &str,
-        todo_id: u64,
-    ) -> Result<PreparedTransaction, AppError> {
-        let pubkey = Pubkey::from_str(public_key)
-            .map_err(|e| AppError::bad_request(format!("Invalid public key: {}", e)))?;
-            
-        // Clone values needed for the blocking operation
-        let program_id = self.program_id;
-        let rpc_url = self.rpc_url.clone();
-        
-        // Use web::block for RPC operations
-        let result = web::block(move || {
-            // Create a new RPC client for this request
-            let rpc_client = RpcClient::new(rpc_url);
-            
-            // Get the latest blockhash
-            let recent_blockhash = rpc_client.get_latest_blockhash()
-                .map_err(|e| AppError::solana(format!("Failed to get recent blockhash: {}", e)))?;
-            
-            // Get the PDAs
-            let (todo_pda, _) = Pubkey::find_program_address(
-                &[b"todo", pubkey.as_ref(), &todo_id.to_le_bytes()],
-                &program_id,
-            );
-            
-            let (user_profile_pda, _) = Pubkey::find_program_address(
-                &[b"user_profile", pubkey.as_ref()],
-                &program_id,
-            );
-            
-            // Build the instruction
-            let instruction = Instruction {
-                program_id,
-                accounts: vec![
-                    AccountMeta::new(user_profile_pda, false),
-                    AccountMeta::new(todo_pda, false),
-                    AccountMeta::new(pubkey, true),
-                ],
-                data: vec![2], // 2 = delete instruction
-            };
-            
-            // Create the transaction
-            let message = Message::new(&[instruction], Some(&pubkey));
-            let transaction = Transaction::new_unsigned(message);
-            
-            // Serialize the transaction
-            let serialized_transaction = general_purpose::STANDARD.encode(
-                bincode::serialize(&transaction)
-                    .map_err(|e| AppError::internal(format!("Failed to serialize transaction: {}", e)))?
-            );
-            
-            Ok::<PreparedTransaction, AppError>(PreparedTransaction {
-                serialized_transaction,
-                transaction_type: "delete_todo".to_string(),
-                metadata: Some(serde_json::to_string(&todo_id)
-                    .map_err(|e| AppError::internal(format!("Failed to serialize metadata: {}", e)))?),
-            })
-        })
-        .await
-        .map_err(|e| AppError::internal(format!("Task execution error: {}", e)))??;
-        
-        Ok(result)
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatusResponse>, AppError> {
+        if let Some(tracked) = self.tracked_status(signature)? {
+            return Ok(Some(tracked));
+        }
+
+        let status = self
+            .rpc_client
+            .get_signature_statuses_with_history(&[*signature])
+            .await
+            .map_err(|e| AppError::solana(format!("Failed to get signature status: {}", e)))?
+            .value
+            .into_iter()
+            .next()
+            .flatten();
+
+        Ok(status.map(|status| Self::status_response(signature, &status)))
     }
 
-    pub async fn submit_signed_transaction(
+    async fn wait_for_confirmation(
         &self,
-        signed_transaction: SignedTransaction,
-    ) -> Result<String, AppError> {
-        // Clone data for the blocking task
-        let rpc_url = self.rpc_url.clone();
-        let transaction_data_clone = signed_transaction.serialized_transaction.clone();
-        
-        // Use tokio::task::spawn_blocking to run the blocking RPC operations
-        let signature = tokio::task::spawn_blocking(move || {
-            // Create a new RPC client inside the blocking task
-            let rpc_client = RpcClient::new(rpc_url);
-          
-            // Decode the transaction
-            let transaction_data = general_purpose::STANDARD.decode(&transaction_data_clone)
-                .map_err(|e| AppError::bad_request(format!("Invalid transaction data: {}", e)))?;
-                
-            let transaction: Transaction = bincode::deserialize(&transaction_data)
-                .map_err(|e| AppError::bad_request(format!("Invalid transaction format: {}", e)))?;
-                
-            // Submit the transaction - this is a blocking operation
-            let signature = rpc_client
-                .send_transaction(&transaction)
-                .map_err(|e| AppError::solana(format!("Failed to submit transaction: {}", e)))?;
-                
-            Ok::<String, AppError>(signature.to_string())
-        })
-        .await
-        .map_err(|e| AppError::internal(format!("Task execution error: {}", e)))??;
-        
-        Ok(signature)
+        signature: &Signature,
+        level: ConfirmationLevel,
+    ) -> Result<TransactionStatusResponse, AppError> {
+        let deadline = tokio::time::Instant::now() + WAIT_FOR_TIMEOUT;
+
+        loop {
+            self.poll_tracked_transactions(Some(*signature)).await?;
+            let status = self
+                .tracked_status(signature)?
+                .ok_or_else(|| AppError::internal("Submitted transaction is not tracked"))?;
+
+            if status.status.satisfies(level)
+                || status.status.is_terminal()
+                || tokio::time::Instant::now() >= deadline
+            {
+                return Ok(status);
+            }
+
+            tokio::time::sleep(TRACKER_POLL_INTERVAL).await;
+        }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use solana_sdk::{
+        hash::Hash,
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use solana_transaction_status::{
+        TransactionConfirmationStatus, TransactionStatus as RpcTransactionStatus,
+    };
+    use solana_todo::TodoError;
+
+    use crate::{
+        models::transaction::{ConfirmationLevel, TransactionStatus},
+        services::{chain::CachedBlockhash, solana::SolanaService},
+    };
+
+    #[test]
+    fn test_cached_blockhash_expiry_and_freshness() {
+        let fetched_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let cached = CachedBlockhash {
+            blockhash: Hash::new_unique(),
+            last_valid_block_height: 1_150,
+            block_height: 1_000,
+            fetched_at,
+        };
+
+        // 150 remaining blocks at 400ms per slot
+        assert_eq!(cached.expires_at(), fetched_at + Duration::seconds(60));
+
+        assert!(cached.is_fresh(fetched_at + Duration::seconds(5)));
+        assert!(!cached.is_fresh(fetched_at + Duration::seconds(30)));
+    }
+
+    fn rpc_status(
+        err: Option<TransactionError>,
+        confirmation_status: Option<TransactionConfirmationStatus>,
+    ) -> RpcTransactionStatus {
+        RpcTransactionStatus {
+            slot: 42,
+            confirmations: None,
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status,
+        }
+    }
+
+    #[test]
+    fn test_status_response_maps_confirmation_levels() {
+        let signature = Keypair::new().sign_message(b"status");
+
+        let confirmed = SolanaService::status_response(
+            &signature,
+            &rpc_status(None, Some(TransactionConfirmationStatus::Confirmed)),
+        );
+        assert_eq!(confirmed.status, TransactionStatus::Confirmed);
+        assert_eq!(confirmed.slot, Some(42));
+        assert!(confirmed.error.is_none());
+
+        let finalized = SolanaService::status_response(
+            &signature,
+            &rpc_status(None, Some(TransactionConfirmationStatus::Finalized)),
+        );
+        assert_eq!(finalized.status, TransactionStatus::Finalized);
+    }
+
+    #[test]
+    fn test_status_response_decodes_program_errors() {
+        let signature = Keypair::new().sign_message(b"status");
+        let code = u32::from(TodoError::DescriptionTooLong);
+        let err = TransactionError::InstructionError(0, InstructionError::Custom(code));
+
+        let failed = SolanaService::status_response(&signature, &rpc_status(Some(err), None));
+        assert_eq!(failed.status, TransactionStatus::Failed);
+        assert!(failed.error.is_some());
+
+        let program_error = failed.program_error.unwrap();
+        assert_eq!(program_error.code, 6000);
+        assert_eq!(program_error.name, "DescriptionTooLong");
+        assert_eq!(program_error.message, TodoError::DescriptionTooLong.to_string());
+
+        // Framework and runtime errors carry no program error details
+        let anchor = TransactionError::InstructionError(0, InstructionError::Custom(2001));
+        assert!(SolanaService::decode_program_error(&anchor).is_none());
+        assert!(SolanaService::decode_program_error(&TransactionError::BlockhashNotFound).is_none());
+    }
+
+    #[test]
+    fn test_transaction_status_satisfies_commitment() {
+        assert!(TransactionStatus::Confirmed.satisfies(ConfirmationLevel::Processed));
+        assert!(TransactionStatus::Confirmed.satisfies(ConfirmationLevel::Confirmed));
+        assert!(!TransactionStatus::Confirmed.satisfies(ConfirmationLevel::Finalized));
+        assert!(TransactionStatus::Finalized.satisfies(ConfirmationLevel::Finalized));
+        assert!(!TransactionStatus::Pending.satisfies(ConfirmationLevel::Processed));
+        assert!(!TransactionStatus::Failed.satisfies(ConfirmationLevel::Processed));
+        assert!(TransactionStatus::Expired.is_terminal());
+    }
+}
@@ -1,55 +1,86 @@
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use anchor_lang::{AccountSerialize, AnchorDeserialize, AnchorSerialize, Discriminator};
+    use base64::{engine::general_purpose, Engine as _};
+    use mockall::predicate::*;
+    use solana_program::system_program;
+    use solana_sdk::{
+        account::Account,
+        compute_budget::{self, ComputeBudgetInstruction},
+        hash::Hash,
+        instruction::{CompiledInstruction, Instruction},
+        message::Message,
+        packet::PACKET_DATA_SIZE,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+    use chrono::{DateTime, Utc};
+    use solana_todo::{
+        instruction,
+        versions::{TodoItemV0, TodoItemV3, UserProfileV0},
+        Delegation, TodoItem, TodoStatus as ProgramTodoStatus, TransferOffer, UserProfile,
+    };
+
     use crate::{
-        error::AppResult,
-        models::todo::{CreateTodoRequest, Todo, UpdateTodoRequest},
-        services::solana::SolanaService,
-        services::todo::TodoService,
+        error::AppError,
+        models::{
+            todo::{
+                CreateTodoRequest, DateTimeWrapper, TodoQuery, TodoResponse, TodoStatus,
+                UpdateTodoRequest,
+            },
+            transaction::{DelegatePermission, PreparedTransaction, SignedTransaction, TransactionStatus},
+        },
+        services::{
+            chain::{CachedBlockhash, MockChainClient},
+            todo::TodoService,
+        },
     };
-    use mockall::predicate::*;
-    use mockall::*;
 
-    // Mock the SolanaService for testing
-    mock! {
-        SolanaServiceMock {}
+    // A chain mock serving the real program id; tests add the calls they expect
+    fn mock_chain() -> MockChainClient {
+        let mut chain = MockChainClient::new();
+        chain.expect_program_id().return_const(solana_todo::ID);
+        chain
+    }
 
-        impl Clone for SolanaServiceMock {
-            fn clone(&self) -> Self;
-        }
+    // Serve a fresh blockhash to every prepare call
+    fn expect_blockhash(chain: &mut MockChainClient) {
+        chain.expect_latest_blockhash().returning(|| {
+            Ok(CachedBlockhash {
+                blockhash: Hash::new_unique(),
+                last_valid_block_height: 150,
+                block_height: 0,
+                fetched_at: Utc::now(),
+            })
+        });
+    }
 
-        #[async_trait::async_trait]
-        impl SolanaService {
-            fn new() -> Result<Self, crate::error::AppError>;
-            async fn get_todos(&self, public_key: &str) -> Result<Vec<Todo>, crate::error::AppError>;
-            async fn create_todo(&self, public_key: &str, todo: CreateTodoRequest) -> Result<Todo, crate::error::AppError>;
-            async fn update_todo(&self, public_key: &str, todo_id: u64, update: UpdateTodoRequest) -> Result<Todo, crate::error::AppError>;
-            async fn delete_todo(&self, public_key: &str, todo_id: u64) -> Result<(), crate::error::AppError>;
-        }
+    fn prepared_transaction(prepared: &PreparedTransaction) -> Transaction {
+        bincode::deserialize(&general_purpose::STANDARD.decode(&prepared.serialized_transaction).unwrap()).unwrap()
+    }
+
+    fn test_service() -> TodoService {
+        TodoService::new(Arc::new(mock_chain()))
     }
 
     #[actix_rt::test]
     async fn test_get_todos() {
         // Setup mock
-        let mut mock = MockSolanaServiceMock::new();
-        mock.expect_get_todos()
-            .with(eq("test_public_key"))
-            .returning(|_| {
-                Ok(vec![
-                    Todo {
-                        id: 1,
-                        description: "Test todo".to_string(),
-                        completed: false,
-                        due_date: 1625097600,
-                        owner: "test_public_key".to_string(),
-                    },
-                ])
-            });
+        let owner = Pubkey::new_unique();
+        let mut mock = mock_chain();
+        mock.expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(owner, 1))));
+        expect_todo_accounts(&mut mock, vec![todo_account(1, "Test todo", owner)]);
 
         // Create service with mock
-        let todo_service = TodoService::new(mock);
+        let todo_service = TodoService::new(Arc::new(mock));
 
         // Call the service
-        let result = todo_service.get_todos("test_public_key").await;
+        let result = todo_service.get_todos(&owner.to_string(), &TodoQuery::default()).await;
 
         // Assert
         assert!(result.is_ok());
@@ -57,29 +88,112 @@ mod tests {
         assert_eq!(todos.len(), 1);
         assert_eq!(todos[0].id, 1);
         assert_eq!(todos[0].description, "Test todo");
-        assert_eq!(todos[0].completed, false);
+        assert!(!todos[0].completed);
     }
 
     #[actix_rt::test]
-    async fn test_create_todo() {
-        // Setup mock
-        let mut mock = MockSolanaServiceMock::new();
-        mock.expect_create_todo()
-            .with(eq("test_public_key"), function(|req: &CreateTodoRequest| {
-                req.description == "New todo" && req.due_date == 1625097600
-            }))
-            .returning(|_, _| {
-                Ok(Todo {
-                    id: 1,
-                    description: "New todo".to_string(),
-                    completed: false,
-                    due_date: 1625097600,
-                    owner: "test_public_key".to_string(),
-                })
-            });
+    async fn test_get_todos_finds_todos_by_owner() {
+        let owner = Pubkey::new_unique();
+        let mut mock = mock_chain();
+        mock.expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(owner, 2))));
+        // Ids 1 and 3 were deleted, so the profile counts two todos
+        mock.expect_get_multiple_accounts().never();
+        expect_todo_accounts(
+            &mut mock,
+            vec![
+                todo_account(2, "Second", owner),
+                todo_account(4, "Fourth", owner),
+                todo_account(1, "Someone else's", Pubkey::new_unique()),
+            ],
+        );
+        let todo_service = TodoService::new(Arc::new(mock));
 
-        // Create service with mock
-        let todo_service = TodoService::new(mock);
+        let todos = todo_service.get_todos(&owner.to_string(), &TodoQuery::default()).await.unwrap();
+        assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_todos_reads_todos_in_older_layouts_by_address() {
+        let owner = Pubkey::new_unique();
+
+        // Todo #1 still keeps its owner after the description, where the
+        // owner query cannot match it
+        let (address, _) = todo_account(1, "Old layout", owner);
+        let mut data = TodoItem::DISCRIMINATOR.to_vec();
+        TodoItemV3 {
+            version: 3,
+            id: 1,
+            description: "Old layout".to_string(),
+            status: ProgramTodoStatus::Todo,
+            due_date: 1625097600,
+            owner,
+            authority: owner,
+            created_at: 1625011200,
+            updated_at: 1625011200,
+            completed_at: None,
+            priority: 0,
+            tags: Vec::new(),
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data.resize(TodoItem::space("Old layout"), 0);
+        let legacy = Account {
+            lamports: 1,
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        let mut mock = mock_chain();
+        mock.expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(owner, 2))));
+        expect_todo_accounts(&mut mock, vec![(address, legacy), todo_account(2, "Current", owner)]);
+        let todo_service = TodoService::new(Arc::new(mock));
+
+        let todos = todo_service.get_todos(&owner.to_string(), &TodoQuery::default()).await.unwrap();
+        assert_eq!(
+            todos.iter().map(|todo| todo.description.as_str()).collect::<Vec<_>>(),
+            vec!["Old layout", "Current"]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_get_todos_filters_by_tag_and_priority() {
+        let owner = Pubkey::new_unique();
+        let mut mock = mock_chain();
+        mock.expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(owner, 6))));
+        expect_todo_accounts(&mut mock, (1..=6).map(|id| todo_account(id, "Todo", owner)).collect());
+        let todo_service = TodoService::new(Arc::new(mock));
+
+        let ids = |todos: Vec<TodoResponse>| todos.iter().map(|todo| todo.id).collect::<Vec<_>>();
+        let query = |tag: Option<&str>, priority: Option<u8>| TodoQuery {
+            tag: tag.map(str::to_string),
+            priority,
+        };
+
+        let work = todo_service.get_todos(&owner.to_string(), &query(Some("work"), None)).await.unwrap();
+        assert_eq!(ids(work), vec![2, 4, 6]);
+
+        let urgent = todo_service.get_todos(&owner.to_string(), &query(None, Some(2))).await.unwrap();
+        assert_eq!(ids(urgent), vec![2, 6]);
+        assert_eq!(
+            todo_service.get_todos(&owner.to_string(), &query(Some("home"), Some(1))).await.map(ids).unwrap(),
+            vec![1, 5]
+        );
+        assert!(todo_service
+            .get_todos(&owner.to_string(), &query(Some("missing"), None))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_create_todo() {
+        let owner = Pubkey::new_unique().to_string();
+        let todo_service = test_service();
 
         // Create request
         let request = CreateTodoRequest {
@@ -88,69 +202,1686 @@ mod tests {
         };
 
         // Call the service
-        let result = todo_service.create_todo("test_public_key", request).await;
+        let result = todo_service.create_todo(&owner, request).await;
 
         // Assert
         assert!(result.is_ok());
         let todo = result.unwrap();
-        assert_eq!(todo.id, 1);
         assert_eq!(todo.description, "New todo");
-        assert_eq!(todo.completed, false);
+        assert!(!todo.completed);
+        assert_eq!(todo.owner, owner);
     }
 
     #[actix_rt::test]
     async fn test_update_todo() {
-        // Setup mock
-        let mut mock = MockSolanaServiceMock::new();
-        mock.expect_update_todo()
-            .with(eq("test_public_key"), eq(1), function(|req: &UpdateTodoRequest| {
-                req.description.as_ref().unwrap() == "Updated todo" && req.completed.unwrap() == true
-            }))
-            .returning(|_, _, _| {
-                Ok(Todo {
-                    id: 1,
-                    description: "Updated todo".to_string(),
-                    completed: true,
-                    due_date: 1625097600,
-                    owner: "test_public_key".to_string(),
-                })
-            });
-
-        // Create service with mock
-        let todo_service = TodoService::new(mock);
+        let owner = Pubkey::new_unique().to_string();
+        let todo_service = test_service();
 
         // Create request
         let request = UpdateTodoRequest {
             description: Some("Updated todo".to_string()),
+            status: None,
             completed: Some(true),
+            due_date: Some(1625184000),
+            priority: None,
+            tags: None,
         };
 
         // Call the service
-        let result = todo_service.update_todo("test_public_key", 1, request).await;
+        let result = todo_service.update_todo(&owner, 1, request).await;
 
         // Assert
         assert!(result.is_ok());
         let todo = result.unwrap();
         assert_eq!(todo.id, 1);
         assert_eq!(todo.description, "Updated todo");
-        assert_eq!(todo.completed, true);
+        assert!(todo.completed);
+        assert_eq!(todo.due_date, 1625184000);
     }
 
     #[actix_rt::test]
-    async fn test_delete_todo() {
-        // Setup mock
-        let mut mock = MockSolanaServiceMock::new();
-        mock.expect_delete_todo()
-            .with(eq("test_public_key"), eq(1))
-            .returning(|_, _| Ok(()));
+    async fn test_update_todo_rejects_negative_due_date() {
+        let request = UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: None,
+            due_date: Some(-1),
+            priority: None,
+            tags: None,
+        };
 
-        // Create service with mock
-        let todo_service = TodoService::new(mock);
+        let result = test_service()
+            .update_todo(&Pubkey::new_unique().to_string(), 1, request)
+            .await;
+
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_todo() {
+        let todo_service = test_service();
 
         // Call the service
-        let result = todo_service.delete_todo("test_public_key", 1).await;
+        let result = todo_service.delete_todo(&Pubkey::new_unique().to_string(), 1).await;
 
         // Assert
         assert!(result.is_ok());
     }
-} 
\ No newline at end of file
+
+    // Split Anchor instruction data into its sighash and Borsh-encoded args
+    fn split_data<T: Discriminator>(ix: &Instruction) -> &[u8] {
+        let (discriminator, args) = ix.data.split_at(8);
+        assert_eq!(discriminator, T::DISCRIMINATOR);
+        args
+    }
+
+    fn account_keys(ix: &Instruction) -> Vec<Pubkey> {
+        ix.accounts.iter().map(|meta| meta.pubkey).collect()
+    }
+
+    // A todo account as allocated before timestamps were recorded: the old
+    // 369-byte size, whose space calculation also missed the length prefix
+    fn legacy_todo_account(id: u64, description: &str, owner: Pubkey) -> Account {
+        let mut data = TodoItem::DISCRIMINATOR.to_vec();
+        TodoItemV0 {
+            id,
+            description: description.to_string(),
+            completed: true,
+            due_date: 1625097600,
+            owner,
+            authority: owner,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data.resize(TodoItemV0::SPACE, 0);
+
+        Account {
+            lamports: 1,
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    // Build a raw program account holding a serialized TodoItem, padded to its allocated size
+    fn todo_account(id: u64, description: &str, owner: Pubkey) -> (Pubkey, Account) {
+        let item = TodoItem {
+            version: TodoItem::VERSION,
+            id,
+            description: description.to_string(),
+            status: if id == 2 { ProgramTodoStatus::Done } else { ProgramTodoStatus::InProgress },
+            due_date: 1625097600,
+            owner,
+            authority: owner,
+            created_at: 1625011200,
+            updated_at: 1625011200 + id as i64,
+            completed_at: (id == 2).then_some(1625050000),
+            priority: (id % 4) as u8,
+            tags: vec![if id.is_multiple_of(2) { "work" } else { "home" }.to_string()],
+        };
+
+        let mut data = Vec::new();
+        item.try_serialize(&mut data).unwrap();
+        data.resize(TodoItem::space(description), 0);
+
+        let account = Account {
+            lamports: 1,
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        (solana_todo::pda::todo_address(&owner, id, &solana_todo::ID).0, account)
+    }
+
+    // Serve `todos` to get_multiple_accounts at their addresses, and to
+    // get_program_accounts by their data; anything else is missing
+    fn expect_todo_accounts(chain: &mut MockChainClient, todos: Vec<(Pubkey, Account)>) {
+        let by_address = todos.clone();
+        chain.expect_get_multiple_accounts().returning(move |addresses| {
+            Ok(addresses
+                .iter()
+                .map(|address| {
+                    by_address
+                        .iter()
+                        .find(|(todo_address, _)| todo_address == address)
+                        .map(|(_, account)| account.clone())
+                })
+                .collect())
+        });
+        chain.expect_get_program_accounts().returning(move |discriminator, filters| {
+            Ok(todos
+                .iter()
+                .filter(|(_, account)| {
+                    let matches = |offset: usize, bytes: &[u8]| account.data.get(offset..offset + bytes.len()) == Some(bytes);
+                    matches(0, &discriminator) && filters.iter().all(|(offset, bytes)| matches(*offset, bytes))
+                })
+                .cloned()
+                .collect())
+        });
+    }
+
+    #[test]
+    fn test_decode_todos_filters_by_owner_and_sorts_by_id() {
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let accounts = vec![
+            todo_account(3, "Third", owner),
+            todo_account(1, "First", owner),
+            todo_account(2, "Someone else's", other),
+            todo_account(2, "Second", owner),
+        ];
+
+        let todos = TodoService::decode_todos(&owner, accounts);
+
+        assert_eq!(todos.len(), 3);
+        assert_eq!(
+            todos.iter().map(|todo| todo.id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(todos[0].description, "First");
+        assert!(todos[1].completed);
+        assert!(todos.iter().all(|todo| todo.owner == owner.to_string()));
+    }
+
+    #[test]
+    fn test_todo_response_surfaces_recorded_times() {
+        let owner = Pubkey::new_unique();
+        let todos = TodoService::decode_todos(
+            &owner,
+            vec![todo_account(1, "Open", owner), todo_account(2, "Done", owner)],
+        );
+        let responses: Vec<TodoResponse> = todos.into_iter().map(TodoResponse::from).collect();
+
+        let timestamp = |time: &Option<DateTimeWrapper>| time.clone().map(|time| DateTime::<Utc>::from(time).timestamp());
+        assert_eq!(timestamp(&responses[0].created_at), Some(1625011200));
+        assert_eq!(timestamp(&responses[0].updated_at), Some(1625011201));
+        assert_eq!(timestamp(&responses[0].completed_at), None);
+        assert_eq!(timestamp(&responses[1].completed_at), Some(1625050000));
+    }
+
+    #[test]
+    fn test_todo_response_reports_status() {
+        let owner = Pubkey::new_unique();
+        let todos = TodoService::decode_todos(
+            &owner,
+            vec![todo_account(1, "Started", owner), todo_account(2, "Done", owner)],
+        );
+        let responses: Vec<TodoResponse> = todos.into_iter().map(TodoResponse::from).collect();
+
+        assert_eq!(responses[0].status, TodoStatus::InProgress);
+        assert!(!responses[0].completed);
+        assert_eq!(responses[1].status, TodoStatus::Done);
+        assert!(responses[1].completed);
+
+        let json = serde_json::to_value(&responses[0]).unwrap();
+        assert_eq!(json["status"], "in_progress");
+        assert_eq!(json["completed"], false);
+    }
+
+    #[test]
+    fn test_validate_description_counts_characters() {
+        // Two bytes per character, so twice the limit in bytes
+        let longest = "é".repeat(TodoItem::MAX_DESCRIPTION_CHARS);
+        assert!(TodoService::validate_description(&longest).is_ok());
+
+        let too_long = "é".repeat(TodoItem::MAX_DESCRIPTION_CHARS + 1);
+        for invalid in ["", too_long.as_str()] {
+            assert!(matches!(
+                TodoService::validate_description(invalid),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+
+        let wallet = Pubkey::new_unique();
+        let update = UpdateTodoRequest {
+            description: Some(too_long),
+            status: None,
+            completed: None,
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+        assert!(matches!(
+            test_service().update_todo_instruction(wallet, wallet, 1, &update),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_priority_and_tags() {
+        let update = |priority: Option<u8>, tags: Option<Vec<&str>>| UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: None,
+            due_date: None,
+            priority,
+            tags: tags.map(|tags| tags.into_iter().map(str::to_string).collect()),
+        };
+        let longest_tag = "x".repeat(TodoItem::MAX_TAG_LEN);
+        // Tags are limited in bytes: ten two-byte characters fill one, and
+        // eleven are too many even though they are fewer than 20 characters
+        let longest_accented_tag = "é".repeat(TodoItem::MAX_TAG_LEN / 2);
+
+        for valid in [
+            update(Some(TodoItem::MAX_PRIORITY), None),
+            update(None, Some(vec![])),
+            update(Some(0), Some(vec!["work", &longest_tag])),
+            update(None, Some(vec![&longest_accented_tag])),
+        ] {
+            assert!(TodoService::validate_priority_and_tags(&valid).is_ok());
+        }
+
+        let too_long = "x".repeat(TodoItem::MAX_TAG_LEN + 1);
+        let too_long_accented = "é".repeat(TodoItem::MAX_TAG_LEN / 2 + 1);
+        for invalid in [
+            update(Some(TodoItem::MAX_PRIORITY + 1), None),
+            update(None, Some(vec!["a", "b", "c", "d", "e", "f"])),
+            update(None, Some(vec![""])),
+            update(None, Some(vec![&too_long])),
+            update(None, Some(vec![&too_long_accented])),
+            update(None, Some(vec!["work", "work"])),
+        ] {
+            assert!(matches!(
+                TodoService::validate_priority_and_tags(&invalid),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+
+        // An update carrying only tags is not empty
+        let wallet = Pubkey::new_unique();
+        let instruction = test_service()
+            .update_todo_instruction(wallet, wallet, 1, &update(None, Some(vec!["work"])))
+            .unwrap();
+        let args = instruction::UpdateTodo::deserialize(&mut &instruction.data[8..]).unwrap();
+        assert_eq!(args.tags, Some(vec!["work".to_string()]));
+        assert_eq!(args.priority, None);
+    }
+
+    #[test]
+    fn test_requested_status_accepts_completed_shorthand() {
+        let update = |status: Option<TodoStatus>, completed: Option<bool>| UpdateTodoRequest {
+            description: None,
+            status,
+            completed,
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        assert_eq!(TodoService::requested_status(&update(None, None)).unwrap(), None);
+        assert_eq!(
+            TodoService::requested_status(&update(None, Some(true))).unwrap(),
+            Some(TodoStatus::Done)
+        );
+        assert_eq!(
+            TodoService::requested_status(&update(None, Some(false))).unwrap(),
+            Some(TodoStatus::Todo)
+        );
+        assert_eq!(
+            TodoService::requested_status(&update(Some(TodoStatus::Blocked), Some(false))).unwrap(),
+            Some(TodoStatus::Blocked)
+        );
+        assert!(matches!(
+            TodoService::requested_status(&update(Some(TodoStatus::Blocked), Some(true))),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_todos_reads_legacy_layout() {
+        let owner = Pubkey::new_unique();
+        let accounts = vec![
+            (Pubkey::new_unique(), legacy_todo_account(1, "Short", owner)),
+            (Pubkey::new_unique(), legacy_todo_account(2, &"x".repeat(276), owner)),
+        ];
+
+        let todos = TodoService::decode_todos(&owner, accounts);
+
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[1].description, "x".repeat(276));
+        assert!(todos.iter().all(|todo| todo.completed));
+        assert!(todos.iter().all(|todo| todo.created_at == 0 && todo.completed_at.is_none()));
+
+        // Times that were never recorded are left out rather than invented
+        let response = TodoResponse::from(todos[0].clone());
+        assert!(response.created_at.is_none());
+        assert!(response.updated_at.is_none());
+        assert!(response.completed_at.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_update_migrates_legacy_todo() {
+        let authority = Pubkey::new_unique();
+        let update = UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: Some(false),
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .returning(move |_| Ok(Some(legacy_todo_account(1, "Old", authority))));
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let prepared = service
+            .prepare_update_transaction(&authority.to_string(), 1, update, None)
+            .await
+            .unwrap();
+        let transaction: Transaction = bincode::deserialize(
+            &general_purpose::STANDARD.decode(prepared.serialized_transaction).unwrap(),
+        )
+        .unwrap();
+        let message = &transaction.message;
+
+        let data: Vec<&[u8]> = message.instructions.iter().map(|ix| &ix.data[..8]).collect();
+        assert_eq!(
+            data,
+            vec![
+                &instruction::MigrateTodo::DISCRIMINATOR[..],
+                &instruction::UpdateTodo::DISCRIMINATOR[..],
+            ]
+        );
+        assert!(prepared.metadata.unwrap().contains("\"migrate\":true"));
+    }
+
+    #[test]
+    fn test_decode_todos_skips_undecodable_accounts() {
+        let owner = Pubkey::new_unique();
+        let (address, mut garbage) = todo_account(1, "Corrupted", owner);
+        garbage.data.truncate(12);
+
+        let todos = TodoService::decode_todos(
+            &owner,
+            vec![(address, garbage), todo_account(2, "Intact", owner)],
+        );
+
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].id, 2);
+    }
+
+    #[test]
+    fn test_initialize_user_instruction_round_trip() {
+        let service = test_service();
+        let authority = Pubkey::new_unique();
+
+        let ix = service.initialize_user_instruction(authority);
+        let args = split_data::<instruction::InitializeUser>(&ix);
+        assert!(args.is_empty());
+
+        let (user_profile, _) = service.get_user_profile_pda(authority);
+        assert_eq!(
+            account_keys(&ix),
+            vec![user_profile, authority, system_program::id()]
+        );
+        assert!(ix.accounts[0].is_writable);
+        assert!(ix.accounts[1].is_signer && ix.accounts[1].is_writable);
+    }
+
+    #[test]
+    fn test_create_todo_instruction_round_trip() {
+        let service = test_service();
+        let authority = Pubkey::new_unique();
+        let request = CreateTodoRequest {
+            description: "Write round-trip tests".to_string(),
+            due_date: 1625097600,
+        };
+
+        let ix = service.create_todo_instruction(authority, 4, &request);
+        assert_eq!(ix.program_id, solana_todo::ID);
+
+        let args =
+            instruction::CreateTodo::try_from_slice(split_data::<instruction::CreateTodo>(&ix))
+                .unwrap();
+        assert_eq!(args.description, request.description);
+        assert_eq!(args.due_date, request.due_date);
+
+        let (user_profile, _) = service.get_user_profile_pda(authority);
+        let (todo_account, _) = service.get_todo_pda(authority, 4);
+        assert_eq!(
+            account_keys(&ix),
+            vec![user_profile, todo_account, authority, system_program::id()]
+        );
+        assert!(ix.accounts[0].is_writable && ix.accounts[1].is_writable);
+        assert!(ix.accounts[2].is_signer && ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_update_todo_instruction_round_trip() {
+        let service = test_service();
+        let authority = Pubkey::new_unique();
+        let update = UpdateTodoRequest {
+            description: Some("Updated".to_string()),
+            status: None,
+            completed: Some(true),
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        let ix = service.update_todo_instruction(authority, authority, 7, &update).unwrap();
+
+        let args =
+            instruction::UpdateTodo::try_from_slice(split_data::<instruction::UpdateTodo>(&ix)).unwrap();
+        assert_eq!(args.description.as_deref(), Some("Updated"));
+        assert_eq!(args.status, Some(ProgramTodoStatus::Done));
+        assert_eq!(args.due_date, None);
+
+        // The owner signs without a delegation, which Anchor encodes as the program id
+        let (todo_account, _) = service.get_todo_pda(authority, 7);
+        assert_eq!(
+            account_keys(&ix),
+            vec![todo_account, authority, authority, system_program::id(), solana_todo::ID]
+        );
+        // The authority pays rent when the description grows the todo, and
+        // the owner is refunded when it shrinks
+        assert!(ix.accounts[1].is_signer && ix.accounts[1].is_writable);
+        assert!(ix.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_update_todo_instruction_rejects_empty_update() {
+        let service = test_service();
+        let wallet = Pubkey::new_unique();
+        let update = UpdateTodoRequest {
+            description: None,
+            status: None,
+            completed: None,
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        assert!(service
+            .update_todo_instruction(wallet, wallet, 1, &update)
+            .is_err());
+    }
+
+    #[test]
+    fn test_delete_todo_instruction_round_trip() {
+        let service = test_service();
+        let authority = Pubkey::new_unique();
+
+        let ix = service.delete_todo_instruction(authority, authority, 2);
+        let args = split_data::<instruction::DeleteTodo>(&ix);
+        assert!(args.is_empty());
+
+        let (user_profile, _) = service.get_user_profile_pda(authority);
+        let (todo_account, _) = service.get_todo_pda(authority, 2);
+        assert_eq!(
+            account_keys(&ix),
+            vec![user_profile, todo_account, authority, authority, solana_todo::ID]
+        );
+    }
+
+    #[test]
+    fn test_delegated_instructions_target_the_owners_accounts() {
+        let service = test_service();
+        let (owner, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let update = UpdateTodoRequest {
+            description: None,
+            status: Some(TodoStatus::Done),
+            completed: None,
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        let (user_profile, _) = service.get_user_profile_pda(owner);
+        let (todo_account, _) = service.get_todo_pda(owner, 3);
+        let (delegation, _) = service.get_delegation_pda(owner, delegate);
+        assert_eq!(
+            delegation,
+            Pubkey::find_program_address(
+                &[b"delegation", owner.as_ref(), delegate.as_ref()],
+                &solana_todo::ID
+            )
+            .0
+        );
+
+        let ix = service.update_todo_instruction(owner, delegate, 3, &update).unwrap();
+        assert_eq!(
+            account_keys(&ix),
+            vec![todo_account, delegate, owner, system_program::id(), delegation]
+        );
+        assert!(ix.accounts[1].is_signer && !ix.accounts[4].is_writable);
+        assert!(ix.accounts[2].is_writable && !ix.accounts[2].is_signer);
+
+        // The owner gets the rent back, so their account is writable
+        let ix = service.delete_todo_instruction(owner, delegate, 3);
+        assert_eq!(
+            account_keys(&ix),
+            vec![user_profile, todo_account, delegate, owner, delegation]
+        );
+        assert!(ix.accounts[2].is_signer && ix.accounts[3].is_writable && !ix.accounts[3].is_signer);
+    }
+
+    fn delegation_account(owner: Pubkey, delegate: Pubkey, permissions: u8) -> Account {
+        let delegation = Delegation {
+            version: Delegation::VERSION,
+            owner,
+            delegate,
+            permissions,
+            created_at: 1625011200,
+            updated_at: 1625011200,
+        };
+        let mut data = Vec::new();
+        delegation.try_serialize(&mut data).unwrap();
+
+        Account {
+            lamports: 1,
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_delegate_grants_or_updates_the_delegation() {
+        let (owner, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let permissions = [DelegatePermission::SetStatus, DelegatePermission::Delete];
+
+        for existing in [false, true] {
+            let mut chain = mock_chain();
+            chain
+                .expect_get_account()
+                .returning(move |_| Ok(existing.then(|| delegation_account(owner, delegate, Delegation::SET_STATUS))));
+            expect_blockhash(&mut chain);
+            let service = TodoService::new(Arc::new(chain));
+
+            let prepared = service
+                .prepare_delegate_transaction(&owner.to_string(), &delegate.to_string(), &permissions)
+                .await
+                .unwrap();
+            let transaction = prepared_transaction(&prepared);
+            let data = &transaction.message.instructions[0].data;
+
+            let expected = Delegation::SET_STATUS | Delegation::DELETE;
+            if existing {
+                assert_eq!(prepared.transaction_type, "update_delegation");
+                assert_eq!(&data[..8], &instruction::UpdateDelegation::DISCRIMINATOR[..]);
+                assert_eq!(instruction::UpdateDelegation::try_from_slice(&data[8..]).unwrap().permissions, expected);
+            } else {
+                assert_eq!(prepared.transaction_type, "grant_delegation");
+                assert_eq!(&data[..8], &instruction::GrantDelegation::DISCRIMINATOR[..]);
+                assert_eq!(instruction::GrantDelegation::try_from_slice(&data[8..]).unwrap().permissions, expected);
+            }
+            assert!(prepared.metadata.unwrap().contains("\"permissions\":[\"set_status\",\"delete\"]"));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_delegate_validates_the_request() {
+        let service = test_service();
+        let owner = Pubkey::new_unique().to_string();
+        let delegate = Pubkey::new_unique().to_string();
+
+        for (delegate, permissions) in [
+            (owner.as_str(), vec![DelegatePermission::Delete]),
+            (delegate.as_str(), vec![]),
+            ("not-a-key", vec![DelegatePermission::Delete]),
+        ] {
+            let result = service.prepare_delegate_transaction(&owner, delegate, &permissions).await;
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_for_owner_requires_a_delegation() {
+        let (owner, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let update = UpdateTodoRequest {
+            description: Some("Shared".to_string()),
+            status: None,
+            completed: None,
+            due_date: None,
+            priority: None,
+            tags: None,
+        };
+
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(|_| Ok(None));
+        let service = TodoService::new(Arc::new(chain));
+
+        let owner = owner.to_string();
+        let result = service
+            .prepare_update_transaction(&delegate.to_string(), 1, update, Some(&owner))
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = service
+            .prepare_delete_transaction(&delegate.to_string(), 1, Some(&owner))
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = service
+            .prepare_revoke_delegation_transaction(&owner, &delegate.to_string())
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_for_owner_checks_the_delegations_permissions() {
+        let (owner, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let service = test_service();
+        let (delegation, _) = service.get_delegation_pda(owner, delegate);
+
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(move |address| {
+            Ok((address == delegation).then(|| delegation_account(owner, delegate, Delegation::SET_STATUS)))
+        });
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+        let (owner, delegate) = (owner.to_string(), delegate.to_string());
+
+        let update = |status: Option<TodoStatus>, description: Option<&str>, priority: Option<u8>| UpdateTodoRequest {
+            description: description.map(str::to_string),
+            status,
+            completed: None,
+            due_date: None,
+            priority,
+            tags: None,
+        };
+        service
+            .prepare_update_transaction(&delegate, 1, update(Some(TodoStatus::Done), None, None), Some(&owner))
+            .await
+            .unwrap();
+
+        // Description edits and deletes were not granted, and priorities are the owner's
+        for update in [
+            update(None, Some("Renamed"), None),
+            update(Some(TodoStatus::Done), Some("Renamed"), None),
+            update(Some(TodoStatus::Done), None, Some(1)),
+        ] {
+            let result = service.prepare_update_transaction(&delegate, 1, update, Some(&owner)).await;
+            assert!(matches!(result, Err(AppError::Forbidden(_))));
+        }
+        let result = service.prepare_delete_transaction(&delegate, 1, Some(&owner)).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_delete_for_owner_leaves_migrations_to_the_owner() {
+        let (owner, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let service = test_service();
+        let (delegation, _) = service.get_delegation_pda(owner, delegate);
+        let (todo_account, _) = service.get_todo_pda(owner, 1);
+
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(move |address| {
+            Ok(if address == delegation {
+                Some(delegation_account(owner, delegate, Delegation::DELETE))
+            } else if address == todo_account {
+                Some(legacy_todo_account(1, "Old", owner))
+            } else {
+                None
+            })
+        });
+        let service = TodoService::new(Arc::new(chain));
+
+        let result = service
+            .prepare_delete_transaction(&delegate.to_string(), 1, Some(&owner.to_string()))
+            .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_transfer_instructions_target_both_wallets() {
+        let service = test_service();
+        let (owner, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let (todo_account, _) = service.get_todo_pda(owner, 3);
+        let (transfer_offer, _) = service.get_transfer_offer_pda(todo_account);
+        assert_eq!(
+            transfer_offer,
+            Pubkey::find_program_address(&[b"transfer", todo_account.as_ref()], &solana_todo::ID).0
+        );
+
+        let ix = service.offer_transfer_instruction(owner, 3, recipient);
+        assert_eq!(
+            account_keys(&ix),
+            vec![transfer_offer, todo_account, recipient, owner, system_program::id()]
+        );
+        assert!(ix.accounts[3].is_signer && !ix.accounts[2].is_signer);
+
+        // The recipient signs and pays for the todo's new account; the owner
+        // gets the rent of the old one and of the offer back
+        let ix = service.transfer_todo_instruction(owner, 3, recipient, 8);
+        assert_eq!(
+            account_keys(&ix),
+            vec![
+                transfer_offer,
+                todo_account,
+                service.get_user_profile_pda(owner).0,
+                owner,
+                service.get_user_profile_pda(recipient).0,
+                service.get_todo_pda(recipient, 8).0,
+                recipient,
+                system_program::id(),
+            ]
+        );
+        assert!(ix.accounts[6].is_signer && ix.accounts[3].is_writable && !ix.accounts[3].is_signer);
+
+        // The new todo must still follow the recipient's last_todo_id on submit
+        let message = Message::new(&[ix], Some(&recipient));
+        assert_eq!(
+            service.create_todo_targets(&message).unwrap(),
+            vec![(recipient, service.get_todo_pda(recipient, 8).0, 1)]
+        );
+
+        let ix = service.cancel_transfer_instruction(owner, 3, recipient);
+        assert_eq!(account_keys(&ix), vec![transfer_offer, owner, recipient]);
+        assert!(ix.accounts[2].is_signer && ix.accounts[1].is_writable);
+    }
+
+    fn transfer_offer_account(todo: Pubkey, owner: Pubkey, recipient: Pubkey) -> Account {
+        let offer = TransferOffer {
+            version: TransferOffer::VERSION,
+            todo,
+            todo_id: 1,
+            todo_created_at: 1625011200,
+            owner,
+            recipient,
+            created_at: 1625011200,
+        };
+        let mut data = Vec::new();
+        offer.try_serialize(&mut data).unwrap();
+
+        Account {
+            lamports: 1,
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_offer_transfer_validates_the_request() {
+        let (owner, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let service = test_service();
+        let (todo_address, _) = service.get_todo_pda(owner, 1);
+        let (transfer_offer, _) = service.get_transfer_offer_pda(todo_address);
+
+        let wallet = owner.to_string();
+        for invalid in [wallet.as_str(), "not-a-key"] {
+            let result = service.prepare_offer_transfer_transaction(&wallet, 1, invalid).await;
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+
+        // A missing todo is not found and one already offered cannot be offered again
+        for offered in [false, true] {
+            let mut chain = mock_chain();
+            chain.expect_get_account().returning(move |address| {
+                Ok(if address == todo_address && offered {
+                    Some(todo_account(1, "Offered", owner).1)
+                } else if address == transfer_offer {
+                    Some(transfer_offer_account(todo_address, owner, recipient))
+                } else {
+                    None
+                })
+            });
+            let service = TodoService::new(Arc::new(chain));
+
+            let result = service
+                .prepare_offer_transfer_transaction(&wallet, 1, &recipient.to_string())
+                .await;
+            if offered {
+                assert!(matches!(result, Err(AppError::BadRequest(_))));
+            } else {
+                assert!(matches!(result, Err(AppError::NotFound(_))));
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_accept_transfer_moves_the_todo_to_the_next_id() {
+        let (owner, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let service = test_service();
+        let (todo_account, _) = service.get_todo_pda(owner, 1);
+        let (transfer_offer, _) = service.get_transfer_offer_pda(todo_account);
+        let (recipient_profile, _) = service.get_user_profile_pda(recipient);
+
+        for has_profile in [false, true] {
+            let mut chain = mock_chain();
+            chain.expect_get_account().returning(move |address| {
+                Ok(if address == transfer_offer {
+                    Some(transfer_offer_account(todo_account, owner, recipient))
+                } else if address == recipient_profile && has_profile {
+                    Some(profile_account(recipient, 4))
+                } else {
+                    None
+                })
+            });
+            expect_blockhash(&mut chain);
+            let service = TodoService::new(Arc::new(chain));
+
+            let prepared = service
+                .prepare_accept_transfer_transaction(&recipient.to_string(), &owner.to_string(), 1)
+                .await
+                .unwrap();
+            let transaction = prepared_transaction(&prepared);
+            assert_eq!(prepared.transaction_type, "transfer_todo");
+
+            // A recipient without a profile gets one before the todo moves in
+            let new_todo_id = if has_profile { 5 } else { 1 };
+            let instructions = &transaction.message.instructions;
+            assert_eq!(instructions.len(), if has_profile { 1 } else { 2 });
+            let transfer = instructions.last().unwrap();
+            assert_eq!(&transfer.data[..8], &instruction::TransferTodo::DISCRIMINATOR[..]);
+            assert_eq!(
+                transaction.message.account_keys[transfer.accounts[5] as usize],
+                service.get_todo_pda(recipient, new_todo_id).0
+            );
+            assert!(prepared
+                .metadata
+                .unwrap()
+                .contains(&format!("\"todo_id\":{}", new_todo_id)));
+        }
+
+        // Only the recipient can accept, and the owner or recipient cancel
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(move |address| {
+            Ok((address == transfer_offer).then(|| transfer_offer_account(todo_account, owner, recipient)))
+        });
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let intruder = Pubkey::new_unique().to_string();
+        let result = service
+            .prepare_accept_transfer_transaction(&intruder, &owner.to_string(), 1)
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        let result = service
+            .prepare_cancel_transfer_transaction(&intruder, 1, Some(&owner.to_string()))
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        for (signer, owner_param) in [(owner, None), (recipient, Some(owner.to_string()))] {
+            let prepared = service
+                .prepare_cancel_transfer_transaction(&signer.to_string(), 1, owner_param.as_deref())
+                .await
+                .unwrap();
+            assert_eq!(prepared.transaction_type, "cancel_transfer");
+            let transaction = prepared_transaction(&prepared);
+            assert_eq!(
+                &transaction.message.instructions[0].data[..8],
+                &instruction::CancelTransfer::DISCRIMINATOR[..]
+            );
+        }
+    }
+
+    // The literal seeds are the on-chain wire format shared with the TypeScript
+    // clients; the backend must derive exactly the accounts the program checks.
+    #[test]
+    fn test_pdas_match_program_seeds() {
+        let service = test_service();
+        let authority = Pubkey::new_unique();
+
+        assert_eq!(solana_todo::pda::USER_PROFILE_SEED, b"user-profile");
+        assert_eq!(solana_todo::pda::TODO_SEED, b"todo");
+
+        assert_eq!(
+            service.get_user_profile_pda(authority),
+            Pubkey::find_program_address(&[b"user-profile", authority.as_ref()], &solana_todo::ID)
+        );
+        assert_eq!(
+            service.get_todo_pda(authority, 3),
+            Pubkey::find_program_address(
+                &[b"todo", authority.as_ref(), &3u64.to_le_bytes()],
+                &solana_todo::ID
+            )
+        );
+    }
+
+    #[test]
+    fn test_create_todo_targets_extracts_todo_accounts() {
+        let service = test_service();
+        let authority = Pubkey::new_unique();
+        let request = CreateTodoRequest {
+            description: "Predicted id".to_string(),
+            due_date: 0,
+        };
+
+        let instructions = vec![
+            service.delete_todo_instruction(authority, authority, 1),
+            service.create_todo_instruction(authority, 5, &request),
+            service.create_todos_batch_instruction(authority, 6, &[request.clone(), request.clone()]),
+        ];
+        let message = Message::new(&instructions, Some(&authority));
+
+        // A batch is checked by the first todo it creates and takes an id
+        // for each of its todos
+        let (todo_account, _) = service.get_todo_pda(authority, 5);
+        let (first_batch_account, _) = service.get_todo_pda(authority, 6);
+        assert_eq!(
+            service.create_todo_targets(&message).unwrap(),
+            vec![(authority, todo_account, 1), (authority, first_batch_account, 2)]
+        );
+    }
+
+    // Sign a delete_todo transaction for `payer`, optionally with extra instructions
+    fn signed_transaction(
+        service: &TodoService,
+        payer: &Keypair,
+        extra: Vec<Instruction>,
+    ) -> Transaction {
+        let mut instructions = vec![service.delete_todo_instruction(payer.pubkey(), payer.pubkey(), 1)];
+        instructions.extend(extra);
+        Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        )
+    }
+
+    fn assert_invalid(result: Result<(), AppError>) {
+        assert!(matches!(result, Err(AppError::InvalidTransaction(_))));
+    }
+
+    #[test]
+    fn test_validate_signed_transaction_accepts_program_transaction() {
+        let service = test_service();
+        let payer = Keypair::new();
+        let transaction = signed_transaction(&service, &payer, vec![]);
+
+        assert!(service
+            .validate_signed_transaction(
+                &transaction,
+                &payer.pubkey(),
+                &transaction.signatures[0].to_string()
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_signed_transaction_rejects_other_fee_payer() {
+        let service = test_service();
+        let payer = Keypair::new();
+        let transaction = signed_transaction(&service, &payer, vec![]);
+
+        assert_invalid(service.validate_signed_transaction(
+            &transaction,
+            &Pubkey::new_unique(),
+            &transaction.signatures[0].to_string(),
+        ));
+    }
+
+    #[test]
+    fn test_validate_signed_transaction_rejects_bad_signatures() {
+        let service = test_service();
+        let payer = Keypair::new();
+        let mut transaction = signed_transaction(&service, &payer, vec![]);
+        let claimed = transaction.signatures[0].to_string();
+
+        // Claimed signature differs from the transaction's
+        assert_invalid(service.validate_signed_transaction(
+            &transaction,
+            &payer.pubkey(),
+            &Keypair::new().sign_message(b"other").to_string(),
+        ));
+
+        // Tampered message no longer matches its signature
+        transaction.message.recent_blockhash = Hash::new_unique();
+        assert_invalid(service.validate_signed_transaction(&transaction, &payer.pubkey(), &claimed));
+    }
+
+    #[test]
+    fn test_validate_signed_transaction_rejects_foreign_programs() {
+        let service = test_service();
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let transaction = signed_transaction(&service, &payer, vec![transfer]);
+
+        assert_invalid(service.validate_signed_transaction(
+            &transaction,
+            &payer.pubkey(),
+            &transaction.signatures[0].to_string(),
+        ));
+    }
+
+    fn profile_account(authority: Pubkey, last_todo_id: u64) -> Account {
+        let mut data = Vec::new();
+        UserProfile {
+            version: UserProfile::VERSION,
+            authority,
+            todo_count: last_todo_id,
+            last_todo_id,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+
+        Account {
+            lamports: 1,
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_create_migrates_unversioned_profile() {
+        let authority = Pubkey::new_unique();
+        let mut data = UserProfile::DISCRIMINATOR.to_vec();
+        UserProfileV0 {
+            authority,
+            todo_count: 1,
+            last_todo_id: 4,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        assert_eq!(data.len(), UserProfileV0::SPACE);
+
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(move |_| {
+            Ok(Some(Account {
+                lamports: 1,
+                data: data.clone(),
+                owner: solana_todo::ID,
+                executable: false,
+                rent_epoch: 0,
+            }))
+        });
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        // The old profile still decodes, so its counter picks the next id
+        let profile = service.get_user_profile(authority).await.unwrap().unwrap();
+        assert_eq!(profile.version, UserProfile::VERSION);
+        assert_eq!(profile.last_todo_id, 4);
+
+        let request = CreateTodoRequest {
+            description: "After the upgrade".to_string(),
+            due_date: 0,
+        };
+        let prepared = service
+            .prepare_create_transaction(&authority.to_string(), request)
+            .await
+            .unwrap();
+        let transaction: Transaction = bincode::deserialize(
+            &general_purpose::STANDARD.decode(prepared.serialized_transaction).unwrap(),
+        )
+        .unwrap();
+
+        let data: Vec<&[u8]> = transaction.message.instructions.iter().map(|ix| &ix.data[..8]).collect();
+        assert_eq!(
+            data,
+            vec![
+                &instruction::MigrateProfile::DISCRIMINATOR[..],
+                &instruction::CreateTodo::DISCRIMINATOR[..],
+            ]
+        );
+        let metadata = prepared.metadata.unwrap();
+        assert!(metadata.contains("\"todo_id\":5"));
+        assert!(metadata.contains("\"migrate_profile\":true"));
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_close_user_closes_every_todo() {
+        let authority = Pubkey::new_unique();
+        let (profile_address, _) = test_service().get_user_profile_pda(authority);
+        let mut todos: Vec<(Pubkey, Account)> = (1..=3).map(|id| todo_account(id, "Mine", authority)).collect();
+        let mut expected: Vec<Pubkey> = todos.iter().map(|(address, _)| *address).collect();
+
+        // Todo #2 has a pending offer, and the deleted todo #4 left one behind
+        let recipient = Pubkey::new_unique();
+        for todo_id in [2, 4] {
+            let todo = test_service().get_todo_pda(authority, todo_id).0;
+            let offer = test_service().get_transfer_offer_pda(todo).0;
+            todos.push((offer, transfer_offer_account(todo, authority, recipient)));
+            expected.push(offer);
+        }
+
+        // The delegations the wallet granted are found by their owner field,
+        // and another wallet's are left alone
+        let delegate = Pubkey::new_unique();
+        let delegation = test_service().get_delegation_pda(authority, delegate).0;
+        todos.push((delegation, delegation_account(authority, delegate, Delegation::SET_STATUS)));
+        expected.push(delegation);
+        let other = Pubkey::new_unique();
+        todos.push((
+            test_service().get_delegation_pda(other, delegate).0,
+            delegation_account(other, delegate, Delegation::SET_STATUS),
+        ));
+        todos.push(todo_account(1, "Someone else's", other));
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .with(eq(profile_address))
+            .returning(move |_| Ok(Some(profile_account(authority, 3))));
+        chain.expect_get_multiple_accounts().never();
+        expect_todo_accounts(&mut chain, todos);
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let prepared = service
+            .prepare_close_user_transactions(&authority.to_string())
+            .await
+            .unwrap();
+        assert_eq!(prepared.len(), 1);
+        let prepared = &prepared[0];
+        assert_eq!(prepared.transaction_type, "close_user");
+        assert!(prepared.metadata.as_deref().unwrap().contains("\"todo_ids\":[1,2,3]"));
+
+        // One instruction closes the profile and everything the wallet still has
+        let transaction = prepared_transaction(prepared);
+        let message = &transaction.message;
+        assert_eq!(message.instructions.len(), 1);
+        let instruction = &message.instructions[0];
+        assert!(instruction.data.starts_with(&instruction::CloseUser::DISCRIMINATOR));
+        let accounts: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .map(|&index| message.account_keys[index as usize])
+            .collect();
+        assert_eq!(accounts[..2], [profile_address, authority]);
+        assert_eq!(accounts[2..], expected[..]);
+        assert!(instruction.accounts[2..].iter().all(|&index| message.is_writable(index as usize)));
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_close_user_requires_a_profile() {
+        let authority = Pubkey::new_unique();
+
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(|_| Ok(None));
+        let service = TodoService::new(Arc::new(chain));
+        assert!(matches!(
+            service.prepare_close_user_transactions(&authority.to_string()).await,
+            Err(AppError::NotFound(_))
+        ));
+
+        // A profile with nothing left in it still closes
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(authority, 0))));
+        expect_todo_accounts(&mut chain, Vec::new());
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+        let prepared = service
+            .prepare_close_user_transactions(&authority.to_string())
+            .await
+            .unwrap();
+        assert_eq!(prepared.len(), 1);
+        let transaction = prepared_transaction(&prepared[0]);
+        let instruction = &transaction.message.instructions[0];
+        assert!(instruction.data.starts_with(&instruction::CloseUser::DISCRIMINATOR));
+        assert_eq!(instruction.accounts.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_close_user_splits_by_transaction_size() {
+        let authority = Pubkey::new_unique();
+        let (profile_address, _) = test_service().get_user_profile_pda(authority);
+        let mut todos: Vec<(Pubkey, Account)> = (1..=70).map(|id| todo_account(id, "Mine", authority)).collect();
+        let todo_addresses: Vec<Pubkey> = todos.iter().map(|(address, _)| *address).collect();
+        let delegate = Pubkey::new_unique();
+        let delegation = test_service().get_delegation_pda(authority, delegate).0;
+        todos.push((delegation, delegation_account(authority, delegate, Delegation::SET_STATUS)));
+
+        let mut profile = UserProfileV0 {
+            authority,
+            todo_count: 70,
+            last_todo_id: 70,
+        }
+        .try_to_vec()
+        .unwrap();
+        profile.splice(0..0, UserProfile::DISCRIMINATOR);
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .with(eq(profile_address))
+            .returning(move |_| {
+                Ok(Some(Account {
+                    lamports: 1,
+                    data: profile.clone(),
+                    owner: solana_todo::ID,
+                    executable: false,
+                    rent_epoch: 0,
+                }))
+            });
+        expect_todo_accounts(&mut chain, todos);
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let prepared = service
+            .prepare_close_user_transactions(&authority.to_string())
+            .await
+            .unwrap();
+        assert!(prepared.len() > 1);
+
+        let mut closed = Vec::new();
+        let count = prepared.len();
+        for (index, prepared) in prepared.iter().enumerate() {
+            let last = index + 1 == count;
+            let transaction = prepared_transaction(prepared);
+            assert!(bincode::serialized_size(&transaction).unwrap() as usize <= PACKET_DATA_SIZE);
+
+            // Only the first transaction migrates the old profile, and only
+            // the last closes it, along with the delegation
+            let message = &transaction.message;
+            let data: Vec<&[u8]> = message.instructions.iter().map(|ix| &ix.data[..8]).collect();
+            let mut expected = vec![if last {
+                &instruction::CloseUser::DISCRIMINATOR[..]
+            } else {
+                &instruction::DeleteTodos::DISCRIMINATOR[..]
+            }];
+            if index == 0 {
+                expected.insert(0, &instruction::MigrateProfile::DISCRIMINATOR[..]);
+            }
+            assert_eq!(data, expected);
+            assert_eq!(prepared.transaction_type, if last { "close_user" } else { "delete_todos" });
+
+            let instruction = message.instructions.last().unwrap();
+            let accounts: Vec<Pubkey> = instruction.accounts[2..]
+                .iter()
+                .map(|&account| message.account_keys[account as usize])
+                .collect();
+            assert_eq!(accounts.contains(&delegation), last);
+            for address in accounts.into_iter().filter(|&address| address != delegation) {
+                closed.push(todo_addresses.iter().position(|&todo| todo == address).unwrap() + 1);
+            }
+        }
+        assert_eq!(closed, (1..=70).collect::<Vec<_>>());
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_close_user_revokes_delegations_in_batches() {
+        let authority = Pubkey::new_unique();
+        let (profile_address, _) = test_service().get_user_profile_pda(authority);
+        let mut accounts = vec![todo_account(1, "Mine", authority)];
+        let delegations: Vec<Pubkey> = (0..60)
+            .map(|_| {
+                let delegate = Pubkey::new_unique();
+                let delegation = test_service().get_delegation_pda(authority, delegate).0;
+                accounts.push((delegation, delegation_account(authority, delegate, Delegation::ALL_PERMISSIONS)));
+                delegation
+            })
+            .collect();
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .with(eq(profile_address))
+            .returning(move |_| Ok(Some(profile_account(authority, 1))));
+        expect_todo_accounts(&mut chain, accounts);
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let prepared = service
+            .prepare_close_user_transactions(&authority.to_string())
+            .await
+            .unwrap();
+        assert!(prepared.len() > 1);
+
+        // Earlier transactions delete the todo and revoke delegations one
+        // instruction each; the last closes the rest with the profile
+        let mut revoked = Vec::new();
+        let count = prepared.len();
+        for (index, prepared) in prepared.iter().enumerate() {
+            let transaction = prepared_transaction(prepared);
+            assert!(bincode::serialized_size(&transaction).unwrap() as usize <= PACKET_DATA_SIZE);
+
+            let message = &transaction.message;
+            let instruction_accounts = |instruction: &solana_sdk::instruction::CompiledInstruction| -> Vec<Pubkey> {
+                instruction
+                    .accounts
+                    .iter()
+                    .map(|&account| message.account_keys[account as usize])
+                    .collect()
+            };
+            for (position, instruction) in message.instructions.iter().enumerate() {
+                let accounts = instruction_accounts(instruction);
+                if index + 1 == count {
+                    assert!(instruction.data.starts_with(&instruction::CloseUser::DISCRIMINATOR));
+                    revoked.extend_from_slice(&accounts[2..]);
+                } else if index == 0 && position == 0 {
+                    assert!(instruction.data.starts_with(&instruction::DeleteTodos::DISCRIMINATOR));
+                    assert_eq!(accounts[2..], [test_service().get_todo_pda(authority, 1).0]);
+                } else {
+                    assert!(instruction.data.starts_with(&instruction::RevokeDelegation::DISCRIMINATOR));
+                    assert_eq!(accounts[1], authority);
+                    revoked.push(accounts[0]);
+                }
+            }
+            let transaction_type = match index {
+                _ if index + 1 == count => "close_user",
+                0 => "delete_todos",
+                _ => "revoke_delegations",
+            };
+            assert_eq!(prepared.transaction_type, transaction_type);
+        }
+        assert_eq!(revoked, delegations);
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_create_batch_packs_todos_into_transactions() {
+        let authority = Pubkey::new_unique();
+        let todos: Vec<CreateTodoRequest> = (1..=80)
+            .map(|n| CreateTodoRequest {
+                description: format!("Sprint item {}", n),
+                due_date: 1625097600 + n,
+            })
+            .collect();
+
+        // A first-time wallet: the profile is created by the first transaction
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(|_| Ok(None));
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let prepared = service
+            .prepare_create_batch_transactions(&authority.to_string(), todos.clone())
+            .await
+            .unwrap();
+        assert!(prepared.len() > 1);
+
+        let mut created = Vec::new();
+        for (index, prepared) in prepared.iter().enumerate() {
+            assert_eq!(prepared.transaction_type, "create_todos_batch");
+            let transaction = prepared_transaction(prepared);
+            assert!(bincode::serialized_size(&transaction).unwrap() as usize <= PACKET_DATA_SIZE);
+
+            let message = &transaction.message;
+            let program = |instruction: &CompiledInstruction| message.account_keys[instruction.program_id_index as usize];
+            let (batch, setup) = message.instructions.split_last().unwrap();
+            let (budget, setup) = setup.split_last().unwrap();
+            assert_eq!(setup.len(), usize::from(index == 0));
+            if index == 0 {
+                assert!(setup[0].data.starts_with(&instruction::InitializeUser::DISCRIMINATOR));
+            }
+
+            // The compute budget is sized to the todos in the transaction
+            let instruction::CreateTodosBatch { todos: batch_todos } =
+                AnchorDeserialize::deserialize(&mut &batch.data[8..]).unwrap();
+            assert_eq!(program(budget), compute_budget::id());
+            assert_eq!(
+                budget.data,
+                ComputeBudgetInstruction::set_compute_unit_limit(
+                    TodoService::create_batch_compute_units(batch_todos.len())
+                )
+                .data
+            );
+
+            // Each todo's account is the PDA of the id it will get
+            for (todo, &account) in batch_todos.iter().zip(&batch.accounts[3..]) {
+                let id = created.len() as u64 + 1;
+                assert_eq!(message.account_keys[account as usize], service.get_todo_pda(authority, id).0);
+                assert_eq!(todo.description, todos[id as usize - 1].description);
+                created.push(id);
+            }
+            assert_eq!(batch.accounts.len(), 3 + batch_todos.len());
+        }
+        assert_eq!(created, (1..=80).collect::<Vec<_>>());
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_create_batch_validates_todos() {
+        let service = test_service();
+        let authority = Pubkey::new_unique().to_string();
+
+        assert!(matches!(
+            service.prepare_create_batch_transactions(&authority, vec![]).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        let todos = vec![
+            CreateTodoRequest {
+                description: "Fine".to_string(),
+                due_date: 0,
+            },
+            CreateTodoRequest {
+                description: String::new(),
+                due_date: 0,
+            },
+        ];
+        assert!(matches!(
+            service.prepare_create_batch_transactions(&authority, todos).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    // A todo account like `todo_account`'s, but marked done
+    fn completed_todo_account(id: u64, owner: Pubkey) -> (Pubkey, Account) {
+        let (address, mut account) = todo_account(id, "Finished", owner);
+        let mut todo = TodoItem::try_deserialize_any_version(&account.data).unwrap();
+        todo.status = ProgramTodoStatus::Done;
+        todo.completed_at = Some(1625050000);
+        let mut data = Vec::new();
+        todo.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        (address, account)
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_clear_completed_splits_by_transaction_size() {
+        let authority = Pubkey::new_unique();
+        let (profile_address, _) = test_service().get_user_profile_pda(authority);
+        let todos: Vec<(Pubkey, Account)> = (1..=60)
+            .map(|id| completed_todo_account(id, authority))
+            .chain((61..=63).map(|id| todo_account(id, "Still open", authority)))
+            .collect();
+        let todo_addresses: Vec<Pubkey> = todos.iter().map(|(address, _)| *address).collect();
+
+        let mut profile = UserProfileV0 {
+            authority,
+            todo_count: 63,
+            last_todo_id: 63,
+        }
+        .try_to_vec()
+        .unwrap();
+        profile.splice(0..0, UserProfile::DISCRIMINATOR);
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .with(eq(profile_address))
+            .returning(move |_| {
+                Ok(Some(Account {
+                    lamports: 1,
+                    data: profile.clone(),
+                    owner: solana_todo::ID,
+                    executable: false,
+                    rent_epoch: 0,
+                }))
+            });
+        expect_todo_accounts(&mut chain, todos);
+        expect_blockhash(&mut chain);
+        let service = TodoService::new(Arc::new(chain));
+
+        let prepared = service
+            .prepare_clear_completed_transactions(&authority.to_string())
+            .await
+            .unwrap();
+        assert!(prepared.len() > 1);
+
+        let mut deleted = Vec::new();
+        for (index, prepared) in prepared.iter().enumerate() {
+            assert_eq!(prepared.transaction_type, "delete_completed_todos");
+            let transaction = prepared_transaction(prepared);
+            assert!(bincode::serialized_size(&transaction).unwrap() as usize <= PACKET_DATA_SIZE);
+
+            // Only the first transaction migrates the old profile
+            let message = &transaction.message;
+            let data: Vec<&[u8]> = message.instructions.iter().map(|ix| &ix.data[..8]).collect();
+            let mut expected = vec![&instruction::DeleteCompletedTodos::DISCRIMINATOR[..]];
+            if index == 0 {
+                expected.insert(0, &instruction::MigrateProfile::DISCRIMINATOR[..]);
+            }
+            assert_eq!(data, expected);
+
+            let instruction = message.instructions.last().unwrap();
+            for &account in &instruction.accounts[2..] {
+                let address = message.account_keys[account as usize];
+                deleted.push(todo_addresses.iter().position(|&todo| todo == address).unwrap() + 1);
+            }
+            let metadata = prepared.metadata.as_deref().unwrap();
+            assert!(metadata.contains(&format!("\"migrate_profile\":{}", index == 0)));
+        }
+        assert_eq!(deleted, (1..=60).collect::<Vec<_>>());
+    }
+
+    #[actix_rt::test]
+    async fn test_prepare_clear_completed_without_completed_todos() {
+        let authority = Pubkey::new_unique();
+
+        let mut chain = mock_chain();
+        chain.expect_get_account().returning(|_| Ok(None));
+        let service = TodoService::new(Arc::new(chain));
+        assert!(matches!(
+            service.prepare_clear_completed_transactions(&authority.to_string()).await,
+            Err(AppError::NotFound(_))
+        ));
+
+        let todos = vec![todo_account(1, "Still open", authority)];
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(authority, 1))));
+        expect_todo_accounts(&mut chain, todos);
+        let service = TodoService::new(Arc::new(chain));
+        let prepared = service
+            .prepare_clear_completed_transactions(&authority.to_string())
+            .await
+            .unwrap();
+        assert!(prepared.is_empty());
+    }
+
+    fn signed_request(transaction: &Transaction) -> SignedTransaction {
+        SignedTransaction {
+            signature: transaction.signatures[0].to_string(),
+            serialized_transaction: general_purpose::STANDARD
+                .encode(bincode::serialize(transaction).unwrap()),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_submit_transaction_sends_through_chain_client() {
+        let payer = Keypair::new();
+        let service = test_service();
+        let transaction = signed_transaction(&service, &payer, vec![]);
+        let signature = transaction.signatures[0];
+
+        let mut chain = mock_chain();
+        chain
+            .expect_send_transaction()
+            .withf(move |sent| sent.signatures[0] == signature)
+            .times(1)
+            .returning(|sent| Ok(sent.signatures[0]));
+        let service = TodoService::new(Arc::new(chain));
+
+        let status = service
+            .submit_transaction(&payer.pubkey().to_string(), signed_request(&transaction), None)
+            .await
+            .unwrap();
+
+        assert_eq!(status.signature, signature.to_string());
+        assert_eq!(status.status, TransactionStatus::Pending);
+    }
+
+    #[actix_rt::test]
+    async fn test_submit_transaction_rejects_stale_todo_id() {
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+        let service = test_service();
+        let request = CreateTodoRequest {
+            description: "Raced".to_string(),
+            due_date: 0,
+        };
+        // Prepared for id 3, but another create already took it
+        let transaction = Transaction::new_signed_with_payer(
+            &[service.create_todo_instruction(authority, 3, &request)],
+            Some(&authority),
+            &[&payer],
+            Hash::new_unique(),
+        );
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(authority, 3))));
+        chain.expect_send_transaction().never();
+        let service = TodoService::new(Arc::new(chain));
+
+        let result = service
+            .submit_transaction(&authority.to_string(), signed_request(&transaction), None)
+            .await;
+
+        assert!(matches!(result, Err(AppError::StaleTransaction(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_submit_transaction_accepts_consecutive_creates() {
+        let payer = Keypair::new();
+        let authority = payer.pubkey();
+        let service = test_service();
+        let request = CreateTodoRequest {
+            description: "One of several".to_string(),
+            due_date: 0,
+        };
+        // Ids 4 and 5, then a batch taking 6 and 7, then 8
+        let consecutive = Transaction::new_signed_with_payer(
+            &[
+                service.create_todo_instruction(authority, 4, &request),
+                service.create_todo_instruction(authority, 5, &request),
+                service.create_todos_batch_instruction(authority, 6, &[request.clone(), request.clone()]),
+                service.create_todo_instruction(authority, 8, &request),
+            ],
+            Some(&authority),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        // Both creates claim id 4
+        let duplicated = Transaction::new_signed_with_payer(
+            &[
+                service.create_todo_instruction(authority, 4, &request),
+                service.create_todo_instruction(authority, 4, &request),
+            ],
+            Some(&authority),
+            &[&payer],
+            Hash::new_unique(),
+        );
+
+        let mut chain = mock_chain();
+        chain
+            .expect_get_account()
+            .returning(move |_| Ok(Some(profile_account(authority, 3))));
+        chain
+            .expect_send_transaction()
+            .times(1)
+            .returning(|sent| Ok(sent.signatures[0]));
+        let service = TodoService::new(Arc::new(chain));
+
+        service
+            .submit_transaction(&authority.to_string(), signed_request(&consecutive), None)
+            .await
+            .unwrap();
+        let result = service
+            .submit_transaction(&authority.to_string(), signed_request(&duplicated), None)
+            .await;
+        assert!(matches!(result, Err(AppError::StaleTransaction(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_get_transaction_status_not_found() {
+        let mut chain = mock_chain();
+        chain.expect_get_transaction_status().returning(|_| Ok(None));
+        let service = TodoService::new(Arc::new(chain));
+
+        let signature = Keypair::new().sign_message(b"unknown").to_string();
+        let result = service.get_transaction_status(&signature).await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+}
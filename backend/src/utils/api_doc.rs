@@ -1,4 +1,3 @@
-use chrono::{DateTime, Utc};
 use utoipa::{
     openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
     Modify, OpenApi,
@@ -6,8 +5,13 @@ use utoipa::{
 
 use crate::models::{
     auth::{AuthRequest, AuthResponse}, 
-    todo::{CreateTodoRequest, TodoResponse, UpdateTodoRequest},
-    transaction::{PreparedTransaction, SignedTransaction, DeleteTodoTransactionRequest}
+    todo::{CreateTodoRequest, TodoResponse, TodoStatus, UpdateTodoRequest},
+    transaction::{
+        AcceptTransferTransactionRequest, CancelTransferTransactionRequest, ConfirmationLevel,
+        CreateTodosBatchTransactionRequest, DelegatePermission, DelegateTransactionRequest, DeleteTodoTransactionRequest,
+        OfferTransferTransactionRequest, PreparedTransaction, ProgramErrorDetails, RevokeDelegationTransactionRequest,
+        SignedTransaction, TransactionStatus, TransactionStatusResponse,
+    }
 };
 use crate::controllers::{auth, todo, transaction};
 
@@ -19,10 +23,20 @@ use crate::controllers::{auth, todo, transaction};
         todo::create_todo,
         todo::update_todo,
         todo::delete_todo,
+        transaction::prepare_initialize_transaction,
         transaction::prepare_create_transaction,
+        transaction::prepare_create_batch_transactions,
         transaction::prepare_update_transaction,
         transaction::prepare_delete_transaction,
-        transaction::submit_transaction
+        transaction::prepare_close_user_transactions,
+        transaction::prepare_clear_completed_transactions,
+        transaction::prepare_delegate_transaction,
+        transaction::prepare_revoke_delegation_transaction,
+        transaction::prepare_offer_transfer_transaction,
+        transaction::prepare_accept_transfer_transaction,
+        transaction::prepare_cancel_transfer_transaction,
+        transaction::submit_transaction,
+        transaction::get_transaction_status
     ),
     components(
         schemas(
@@ -31,9 +45,21 @@ use crate::controllers::{auth, todo, transaction};
             CreateTodoRequest,
             UpdateTodoRequest,
             TodoResponse,
+            TodoStatus,
             PreparedTransaction,
             SignedTransaction,
-            DeleteTodoTransactionRequest
+            CreateTodosBatchTransactionRequest,
+            DeleteTodoTransactionRequest,
+            DelegatePermission,
+            DelegateTransactionRequest,
+            RevokeDelegationTransactionRequest,
+            OfferTransferTransactionRequest,
+            AcceptTransferTransactionRequest,
+            CancelTransferTransactionRequest,
+            TransactionStatus,
+            TransactionStatusResponse,
+            ProgramErrorDetails,
+            ConfirmationLevel
         )
     ),
     tags(
@@ -0,0 +1,886 @@
+// End-to-end tests running the backend's HTTP API against the real
+// solana_todo program on the in-memory chain, so instruction encoding, PDAs
+// and account decoding are all checked against the program itself.
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test, web, App};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use anchor_lang::{AccountSerialize, AnchorDeserialize, AnchorSerialize, Discriminator};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    compute_budget::{self, ComputeBudgetInstruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program, sysvar,
+    transaction::Transaction,
+};
+use solana_todo::{
+    pda,
+    versions::{TodoItemV0, UserProfileV0},
+    Delegation, TodoCreated, TodoItem, TodoStatus as ProgramTodoStatus, TodoStatusChanged, UserInitialized, UserProfile,
+};
+
+use solana_todo_backend::{
+    controllers::{
+        authenticate, get_todos, prepare_accept_transfer_transaction, prepare_cancel_transfer_transaction,
+        prepare_clear_completed_transactions, prepare_close_user_transactions, prepare_create_batch_transactions,
+        prepare_create_transaction, prepare_delegate_transaction, prepare_delete_transaction,
+        prepare_offer_transfer_transaction, prepare_revoke_delegation_transaction, prepare_update_transaction,
+        submit_transaction,
+    },
+    middlewares::Authentication,
+    models::{
+        auth::{AuthRequest, AuthResponse},
+        todo::{CreateTodoRequest, TodoResponse, UpdateTodoRequest},
+        transaction::{
+            AcceptTransferTransactionRequest, CancelTransferTransactionRequest, DelegatePermission,
+            DelegateTransactionRequest, DeleteTodoTransactionRequest, OfferTransferTransactionRequest,
+            PreparedTransaction, RevokeDelegationTransactionRequest, SignedTransaction, TransactionStatus,
+            TransactionStatusResponse,
+        },
+    },
+    services::{decode_events, AuthService, ChainClient, InMemoryChain, TodoEvent, TodoService},
+};
+
+fn init_test_env() {
+    std::env::set_var("SOLANA_PROGRAM_ID", solana_todo::ID.to_string());
+    std::env::set_var("JWT_SECRET", "test_jwt_secret");
+    // Where `cargo build-sbf` writes the program, unless pointed elsewhere
+    if std::env::var_os("SBF_OUT_DIR").is_none() {
+        std::env::set_var("SBF_OUT_DIR", concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy"));
+    }
+}
+
+// Start a bank running the program, with `wallet` funded to pay for accounts
+async fn start_chain(wallet: &Keypair) -> Arc<dyn ChainClient> {
+    start_chain_with(wallet, Vec::new()).await
+}
+
+// Like start_chain, with `accounts` already on chain
+async fn start_chain_with(wallet: &Keypair, mut accounts: Vec<(Pubkey, Account)>) -> Arc<dyn ChainClient> {
+    accounts.push((
+        wallet.pubkey(),
+        Account::new(10 * LAMPORTS_PER_SOL, 0, &system_program::id()),
+    ));
+    // The compiled program, as a validator runs it. SOLANA_TODO_NATIVE=1
+    // runs the native build instead where the SBF toolchain is missing.
+    let chain = if std::env::var_os("SOLANA_TODO_NATIVE").is_some() {
+        InMemoryChain::start_with_accounts(solana_todo::ID, accounts).await
+    } else {
+        InMemoryChain::start_sbf_with_accounts(solana_todo::ID, accounts).await
+    };
+    Arc::new(chain.unwrap_or_else(|e| panic!("{}", e)))
+}
+
+async fn get_test_app(
+    chain: Arc<dyn ChainClient>,
+) -> impl actix_web::dev::Service<
+    actix_http::Request,
+    Response = actix_web::dev::ServiceResponse,
+    Error = actix_web::Error,
+> {
+    let todo_service = TodoService::new(chain.clone());
+    let auth_service = AuthService::new(chain);
+
+    test::init_service(
+        App::new()
+            .app_data(web::Data::new(auth_service.clone()))
+            .app_data(web::Data::new(todo_service))
+            .service(
+                web::scope("/api")
+                    .service(authenticate)
+                    .service(
+                        web::scope("/todos")
+                            .wrap(Authentication::new(auth_service.clone()))
+                            .service(get_todos),
+                    )
+                    .service(
+                        web::scope("/transactions")
+                            .wrap(Authentication::new(auth_service))
+                            .service(prepare_create_transaction)
+                            .service(prepare_create_batch_transactions)
+                            .service(prepare_update_transaction)
+                            .service(prepare_delete_transaction)
+                            .service(prepare_close_user_transactions)
+                            .service(prepare_clear_completed_transactions)
+                            .service(prepare_delegate_transaction)
+                            .service(prepare_revoke_delegation_transaction)
+                            .service(prepare_offer_transfer_transaction)
+                            .service(prepare_accept_transfer_transaction)
+                            .service(prepare_cancel_transfer_transaction)
+                            .service(submit_transaction),
+                    ),
+            ),
+    )
+    .await
+}
+
+// Sign the wallet in and return its bearer header
+async fn sign_in<S>(app: &S, wallet: &Keypair) -> (&'static str, String)
+where
+    S: actix_web::dev::Service<actix_http::Request, Response = actix_web::dev::ServiceResponse, Error = actix_web::Error>,
+{
+    let timestamp = Utc::now().timestamp();
+    let message = format!("Sign in to Solana Todo App: {}", timestamp);
+    let req = test::TestRequest::post()
+        .uri("/api/auth")
+        .set_json(&AuthRequest {
+            public_key: wallet.pubkey().to_string(),
+            signature: wallet.sign_message(message.as_bytes()).to_string(),
+            timestamp,
+        })
+        .to_request();
+    let auth: AuthResponse = test::call_and_read_body_json(app, req).await;
+
+    ("Authorization", format!("Bearer {}", auth.token))
+}
+
+// Fetch a prepared transaction from `uri` and sign it with the wallet
+async fn prepare_and_sign<S, T>(app: &S, bearer: &(&'static str, String), uri: &str, body: &T, wallet: &Keypair) -> SignedTransaction
+where
+    S: actix_web::dev::Service<actix_http::Request, Response = actix_web::dev::ServiceResponse, Error = actix_web::Error>,
+    T: serde::Serialize,
+{
+    let req = test::TestRequest::post()
+        .uri(uri)
+        .append_header(bearer.clone())
+        .set_json(body)
+        .to_request();
+    let prepared: PreparedTransaction = test::call_and_read_body_json(app, req).await;
+    sign_prepared(&prepared, wallet)
+}
+
+fn sign_prepared(prepared: &PreparedTransaction, wallet: &Keypair) -> SignedTransaction {
+    let mut transaction: Transaction = bincode::deserialize(
+        &general_purpose::STANDARD
+            .decode(&prepared.serialized_transaction)
+            .unwrap(),
+    )
+    .unwrap();
+    let blockhash = transaction.message.recent_blockhash;
+    transaction.sign(&[wallet], blockhash);
+
+    SignedTransaction {
+        signature: transaction.signatures[0].to_string(),
+        serialized_transaction: general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap()),
+    }
+}
+
+async fn submit<S>(app: &S, bearer: &(&'static str, String), signed: &SignedTransaction) -> actix_web::dev::ServiceResponse
+where
+    S: actix_web::dev::Service<actix_http::Request, Response = actix_web::dev::ServiceResponse, Error = actix_web::Error>,
+{
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/submit?wait_for=finalized")
+        .append_header(bearer.clone())
+        .set_json(signed)
+        .to_request();
+    test::call_service(app, req).await
+}
+
+// Close the wallet's profile, signing and submitting the prepared
+// transactions in order; returns how many there were
+async fn close_profile<S>(app: &S, bearer: &(&'static str, String), wallet: &Keypair) -> usize
+where
+    S: actix_web::dev::Service<actix_http::Request, Response = actix_web::dev::ServiceResponse, Error = actix_web::Error>,
+{
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/close")
+        .append_header(bearer.clone())
+        .to_request();
+    let prepared: Vec<PreparedTransaction> = test::call_and_read_body_json(app, req).await;
+    for prepared in &prepared {
+        let signed = sign_prepared(prepared, wallet);
+        assert_eq!(submit(app, bearer, &signed).await.status(), StatusCode::OK);
+    }
+    prepared.len()
+}
+
+async fn list_todos<S>(app: &S, bearer: &(&'static str, String)) -> Vec<TodoResponse>
+where
+    S: actix_web::dev::Service<actix_http::Request, Response = actix_web::dev::ServiceResponse, Error = actix_web::Error>,
+{
+    list_todos_matching(app, bearer, "").await
+}
+
+async fn list_todos_matching<S>(app: &S, bearer: &(&'static str, String), query: &str) -> Vec<TodoResponse>
+where
+    S: actix_web::dev::Service<actix_http::Request, Response = actix_web::dev::ServiceResponse, Error = actix_web::Error>,
+{
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/todos{}", query))
+        .append_header(bearer.clone())
+        .to_request();
+    test::call_and_read_body_json(app, req).await
+}
+
+fn create_request(description: &str) -> CreateTodoRequest {
+    CreateTodoRequest {
+        description: description.to_string(),
+        due_date: 1625097600,
+    }
+}
+
+#[actix_rt::test]
+async fn test_todo_lifecycle_against_program() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let app = get_test_app(start_chain(&wallet).await).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    // The first create also initializes the wallet's profile
+    for description in ["Write the harness", "Ship it"] {
+        let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request(description), &wallet).await;
+        let resp = submit(&app, &bearer, &signed).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let status: TransactionStatusResponse = test::read_body_json(resp).await;
+        assert_eq!(status.status, TransactionStatus::Finalized);
+    }
+
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(todos[0].description, "Write the harness");
+    assert!(todos.iter().all(|todo| todo.owner == wallet.pubkey().to_string()));
+
+    // Update every field of the first todo in one instruction
+    let update = UpdateTodoRequest {
+        description: Some("Write the program-test harness".to_string()),
+        status: None,
+        completed: Some(true),
+        due_date: Some(1625184000),
+        priority: Some(2),
+        tags: Some(vec!["testing".to_string()]),
+    };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/update/1", &update, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let tagged = list_todos_matching(&app, &bearer, "?tag=testing&priority=2").await;
+    assert_eq!(tagged.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1]);
+    assert!(list_todos_matching(&app, &bearer, "?priority=3").await.is_empty());
+
+    // Delete the second one
+    let delete = DeleteTodoTransactionRequest { todo_id: 2, owner: None };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/delete", &delete, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.len(), 1);
+    assert_eq!(todos[0].id, 1);
+    assert_eq!(todos[0].description, "Write the program-test harness");
+    assert!(todos[0].completed);
+    assert_eq!(todos[0].due_date, 1625184000);
+    assert_eq!(todos[0].priority, 2);
+    assert_eq!(todos[0].tags, vec!["testing"]);
+}
+
+#[actix_rt::test]
+async fn test_racing_creates_are_rejected_as_stale() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let app = get_test_app(start_chain(&wallet).await).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    // Both are prepared for todo id 1
+    let first = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request("First"), &wallet).await;
+    let second = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request("Second"), &wallet).await;
+
+    assert_eq!(submit(&app, &bearer, &first).await.status(), StatusCode::OK);
+    assert_eq!(submit(&app, &bearer, &second).await.status(), StatusCode::CONFLICT);
+
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.len(), 1);
+    assert_eq!(todos[0].description, "First");
+}
+
+// A program account holding `value` behind `discriminator`, allocated at `space` bytes
+fn stored_account<T: AnchorSerialize>(discriminator: [u8; 8], value: &T, space: usize) -> Account {
+    let mut data = discriminator.to_vec();
+    value.serialize(&mut data).unwrap();
+    data.resize(space, 0);
+
+    Account {
+        lamports: Rent::default().minimum_balance(space),
+        data,
+        owner: solana_todo::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[actix_rt::test]
+async fn test_unversioned_accounts_are_migrated_on_demand() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let owner = wallet.pubkey();
+
+    // A profile and two todos written before accounts carried a version
+    let mut accounts = vec![(
+        pda::user_profile_address(&owner, &solana_todo::ID).0,
+        stored_account(
+            UserProfile::DISCRIMINATOR,
+            &UserProfileV0 {
+                authority: owner,
+                todo_count: 2,
+                last_todo_id: 2,
+            },
+            UserProfileV0::SPACE,
+        ),
+    )];
+    for id in [1, 2] {
+        let todo = TodoItemV0 {
+            id,
+            description: format!("Old #{}", id),
+            completed: false,
+            due_date: 1625097600,
+            owner,
+            authority: owner,
+        };
+        accounts.push((
+            pda::todo_address(&owner, id, &solana_todo::ID).0,
+            stored_account(TodoItem::DISCRIMINATOR, &todo, TodoItemV0::SPACE),
+        ));
+    }
+
+    let app = get_test_app(start_chain_with(&wallet, accounts).await).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    // Old accounts are listed before anything is migrated
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 2]);
+
+    // Each prepared transaction migrates the accounts its instruction reads
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request("New"), &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let update = UpdateTodoRequest {
+        description: None,
+        status: None,
+        completed: None,
+        due_date: None,
+        priority: Some(1),
+        tags: None,
+    };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/update/1", &update, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let delete = DeleteTodoTransactionRequest { todo_id: 2, owner: None };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/delete", &delete, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 3]);
+    assert_eq!(todos[0].description, "Old #1");
+    assert_eq!(todos[0].priority, 1);
+    assert_eq!(todos[1].description, "New");
+}
+
+#[actix_rt::test]
+async fn test_offboarding_closes_profile_todos_and_delegations_in_one_transaction() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let owner = wallet.pubkey();
+    let chain = start_chain(&wallet).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    for description in ["Hand over", "Return laptop"] {
+        let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request(description), &wallet).await;
+        assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    }
+    let collaborator = Keypair::new().pubkey();
+    let delegate = DelegateTransactionRequest {
+        delegate: collaborator.to_string(),
+        permissions: vec![DelegatePermission::SetStatus],
+    };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/delegate", &delegate, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    assert_eq!(close_profile(&app, &bearer, &wallet).await, 1);
+
+    // The delegation goes too, so a later profile does not inherit it
+    assert!(list_todos(&app, &bearer).await.is_empty());
+    let profile = pda::user_profile_address(&owner, &solana_todo::ID).0;
+    assert!(chain.get_account(profile).await.unwrap().is_none());
+    let delegation = pda::delegation_address(&owner, &collaborator, &solana_todo::ID).0;
+    assert!(chain.get_account(delegation).await.unwrap().is_none());
+
+    // With no profile left there is nothing to close
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/close")
+        .append_header(bearer.clone())
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_offboarding_a_long_backlog_spans_transactions() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let owner = wallet.pubkey();
+    let chain = start_chain(&wallet).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    let todos: Vec<CreateTodoRequest> = (1..=60).map(|n| create_request(&format!("Backlog item {}", n))).collect();
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/create-batch")
+        .append_header(bearer.clone())
+        .set_json(serde_json::json!({ "todos": todos }))
+        .to_request();
+    let prepared: Vec<PreparedTransaction> = test::call_and_read_body_json(&app, req).await;
+    for prepared in &prepared {
+        let signed = sign_prepared(prepared, &wallet);
+        assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    }
+    let offer = OfferTransferTransactionRequest {
+        todo_id: 7,
+        recipient: Keypair::new().pubkey().to_string(),
+    };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/offer-transfer", &offer, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    let collaborator = Keypair::new().pubkey();
+    let delegate = DelegateTransactionRequest {
+        delegate: collaborator.to_string(),
+        permissions: vec![DelegatePermission::SetStatus],
+    };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/delegate", &delegate, &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    // Too many todos for one close_user, so earlier transactions delete
+    // todos and the last one closes the profile
+    assert!(close_profile(&app, &bearer, &wallet).await > 1);
+
+    assert!(list_todos(&app, &bearer).await.is_empty());
+    let todo = pda::todo_address(&owner, 7, &solana_todo::ID).0;
+    for address in [
+        pda::user_profile_address(&owner, &solana_todo::ID).0,
+        pda::transfer_offer_address(&todo, &solana_todo::ID).0,
+        pda::delegation_address(&owner, &collaborator, &solana_todo::ID).0,
+    ] {
+        assert!(chain.get_account(address).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_offboarding_revokes_more_delegations_than_fit_in_one_transaction() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let owner = wallet.pubkey();
+
+    // More collaborators than one close_user could list
+    let delegations: Vec<(Pubkey, Account)> = (0..50)
+        .map(|_| {
+            let delegate = Keypair::new().pubkey();
+            let mut data = Vec::new();
+            Delegation {
+                version: Delegation::VERSION,
+                owner,
+                delegate,
+                permissions: Delegation::ALL_PERMISSIONS,
+                created_at: 1_700_000_000,
+                updated_at: 1_700_000_000,
+            }
+            .try_serialize(&mut data)
+            .unwrap();
+            let account = Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: solana_todo::ID,
+                executable: false,
+                rent_epoch: 0,
+            };
+            (pda::delegation_address(&owner, &delegate, &solana_todo::ID).0, account)
+        })
+        .collect();
+    let addresses: Vec<Pubkey> = delegations.iter().map(|(address, _)| *address).collect();
+    let chain = start_chain_with(&wallet, delegations).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request("Last one"), &wallet).await;
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    assert!(close_profile(&app, &bearer, &wallet).await > 1);
+
+    assert!(chain
+        .get_account(pda::user_profile_address(&owner, &solana_todo::ID).0)
+        .await
+        .unwrap()
+        .is_none());
+    for address in addresses {
+        assert!(chain.get_account(address).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_clear_completed_deletes_finished_todos() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let chain = start_chain(&wallet).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    for description in ["Ship it", "Write the changelog", "Celebrate"] {
+        let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request(description), &wallet).await;
+        assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    }
+    let done = UpdateTodoRequest {
+        description: None,
+        status: None,
+        completed: Some(true),
+        due_date: None,
+        priority: None,
+        tags: None,
+    };
+    for id in [1, 3] {
+        let uri = format!("/api/transactions/prepare/update/{}", id);
+        let signed = prepare_and_sign(&app, &bearer, &uri, &done, &wallet).await;
+        assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/clear-completed")
+        .append_header(bearer.clone())
+        .to_request();
+    let prepared: Vec<PreparedTransaction> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(prepared.len(), 1);
+    let signed = sign_prepared(&prepared[0], &wallet);
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![2]);
+
+    // Nothing left to clear
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/clear-completed")
+        .append_header(bearer.clone())
+        .to_request();
+    let prepared: Vec<PreparedTransaction> = test::call_and_read_body_json(&app, req).await;
+    assert!(prepared.is_empty());
+}
+
+#[tokio::test]
+async fn test_batch_create_spans_transactions() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let chain = start_chain(&wallet).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    let todos: Vec<CreateTodoRequest> = (1..=30).map(|n| create_request(&format!("Backlog item {}", n))).collect();
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/create-batch")
+        .append_header(bearer.clone())
+        .set_json(serde_json::json!({ "todos": todos }))
+        .to_request();
+    let prepared: Vec<PreparedTransaction> = test::call_and_read_body_json(&app, req).await;
+    assert!(prepared.len() > 1);
+
+    for prepared in &prepared {
+        let signed = sign_prepared(prepared, &wallet);
+        assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    }
+
+    let todos = list_todos(&app, &bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), (1..=30).collect::<Vec<_>>());
+    assert_eq!(todos[29].description, "Backlog item 30");
+}
+
+#[tokio::test]
+async fn test_largest_create_batch_fits_its_compute_limit() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let chain = start_chain(&wallet).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+
+    // The shortest descriptions pack the most todos into each transaction
+    let todos: Vec<CreateTodoRequest> = (0..120).map(|_| create_request("x")).collect();
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/create-batch")
+        .append_header(bearer.clone())
+        .set_json(serde_json::json!({ "todos": todos }))
+        .to_request();
+    let prepared: Vec<PreparedTransaction> = test::call_and_read_body_json(&app, req).await;
+
+    let compute_limit = |transaction: &Transaction| {
+        let message = &transaction.message;
+        message
+            .instructions
+            .iter()
+            .filter(|instruction| message.account_keys[instruction.program_id_index as usize] == compute_budget::id())
+            .find_map(|instruction| match ComputeBudgetInstruction::try_from_slice(&instruction.data) {
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => Some(units as u64),
+                _ => None,
+            })
+            .unwrap()
+    };
+    // Run under SBF, the program fails a transaction that needs more units
+    // than the limit the backend requested. Each batch is checked in turn,
+    // as it builds on the todos of the ones before it.
+    for prepared in &prepared {
+        let signed = sign_prepared(prepared, &wallet);
+        let transaction: Transaction =
+            bincode::deserialize(&general_purpose::STANDARD.decode(&signed.serialized_transaction).unwrap()).unwrap();
+        let simulation = chain.simulate_transaction(&transaction).await.unwrap();
+        assert!(simulation.error.is_none(), "simulation failed: {:?}", simulation.logs);
+        assert!(simulation.units_consumed.unwrap() <= compute_limit(&transaction));
+
+        assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+    }
+    assert_eq!(list_todos(&app, &bearer).await.len(), 120);
+}
+
+#[actix_rt::test]
+async fn test_collaborator_updates_and_deletes_delegated_todos() {
+    init_test_env();
+    let (owner, collaborator) = (Keypair::new(), Keypair::new());
+    let funded = Account::new(10 * LAMPORTS_PER_SOL, 0, &system_program::id());
+    let app = get_test_app(start_chain_with(&owner, vec![(collaborator.pubkey(), funded)]).await).await;
+    let owner_bearer = sign_in(&app, &owner).await;
+    let collaborator_bearer = sign_in(&app, &collaborator).await;
+
+    for description in ["Shared chore", "Another chore"] {
+        let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/create", &create_request(description), &owner).await;
+        assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    }
+
+    let delegate = DelegateTransactionRequest {
+        delegate: collaborator.pubkey().to_string(),
+        permissions: vec![DelegatePermission::SetStatus, DelegatePermission::EditDescription],
+    };
+    let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/delegate", &delegate, &owner).await;
+    assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+
+    // The collaborator names the owner to work on their todos
+    let update = UpdateTodoRequest {
+        description: Some("Shared chore, done together".to_string()),
+        status: None,
+        completed: Some(true),
+        due_date: None,
+        priority: None,
+        tags: None,
+    };
+    let uri = format!("/api/transactions/prepare/update/1?owner={}", owner.pubkey());
+    let signed = prepare_and_sign(&app, &collaborator_bearer, &uri, &update, &collaborator).await;
+    assert_eq!(submit(&app, &collaborator_bearer, &signed).await.status(), StatusCode::OK);
+
+    // Deleting was not granted, so it cannot even be prepared until the
+    // owner widens the delegation
+    let delete = DeleteTodoTransactionRequest {
+        todo_id: 2,
+        owner: Some(owner.pubkey().to_string()),
+    };
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/delete")
+        .append_header(collaborator_bearer.clone())
+        .set_json(&delete)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let delegate = DelegateTransactionRequest {
+        permissions: vec![DelegatePermission::Delete],
+        ..delegate
+    };
+    let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/delegate", &delegate, &owner).await;
+    assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    let signed = prepare_and_sign(&app, &collaborator_bearer, "/api/transactions/prepare/delete", &delete, &collaborator).await;
+    assert_eq!(submit(&app, &collaborator_bearer, &signed).await.status(), StatusCode::OK);
+
+    let todos = list_todos(&app, &owner_bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(todos[0].description, "Shared chore, done together");
+    assert!(todos[0].completed);
+
+    // After revoking, the collaborator cannot even prepare a change
+    let revoke = RevokeDelegationTransactionRequest {
+        delegate: collaborator.pubkey().to_string(),
+    };
+    let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/revoke-delegation", &revoke, &owner).await;
+    assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    let req = test::TestRequest::post()
+        .uri(&uri)
+        .append_header(collaborator_bearer.clone())
+        .set_json(&update)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_todo_is_offered_and_accepted_by_another_wallet() {
+    init_test_env();
+    let (owner, recipient) = (Keypair::new(), Keypair::new());
+    let funded = Account::new(10 * LAMPORTS_PER_SOL, 0, &system_program::id());
+    let app = get_test_app(start_chain_with(&owner, vec![(recipient.pubkey(), funded)]).await).await;
+    let owner_bearer = sign_in(&app, &owner).await;
+    let recipient_bearer = sign_in(&app, &recipient).await;
+
+    for description in ["Hand over", "Keep for now"] {
+        let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/create", &create_request(description), &owner).await;
+        assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    }
+    for todo_id in [1, 2] {
+        let offer = OfferTransferTransactionRequest {
+            todo_id,
+            recipient: recipient.pubkey().to_string(),
+        };
+        let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/offer-transfer", &offer, &owner).await;
+        assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    }
+
+    // The recipient has no profile yet, so accepting creates it and the todo
+    // becomes their first
+    let accept = AcceptTransferTransactionRequest {
+        owner: owner.pubkey().to_string(),
+        todo_id: 1,
+    };
+    let signed = prepare_and_sign(&app, &recipient_bearer, "/api/transactions/prepare/accept-transfer", &accept, &recipient).await;
+    assert_eq!(submit(&app, &recipient_bearer, &signed).await.status(), StatusCode::OK);
+
+    // The second offer is declined, after which it can no longer be accepted
+    let decline = CancelTransferTransactionRequest {
+        todo_id: 2,
+        owner: Some(owner.pubkey().to_string()),
+    };
+    let signed = prepare_and_sign(&app, &recipient_bearer, "/api/transactions/prepare/cancel-transfer", &decline, &recipient).await;
+    assert_eq!(submit(&app, &recipient_bearer, &signed).await.status(), StatusCode::OK);
+    let req = test::TestRequest::post()
+        .uri("/api/transactions/prepare/accept-transfer")
+        .append_header(recipient_bearer.clone())
+        .set_json(&AcceptTransferTransactionRequest { todo_id: 2, ..accept })
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    let todos = list_todos(&app, &owner_bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![2]);
+    let todos = list_todos(&app, &recipient_bearer).await;
+    assert_eq!(todos.len(), 1);
+    assert_eq!((todos[0].id, todos[0].description.as_str()), (1, "Hand over"));
+}
+
+#[actix_rt::test]
+async fn test_offers_close_with_their_todos_and_never_move_a_recreated_one() {
+    init_test_env();
+    let (owner, recipient) = (Keypair::new(), Keypair::new());
+    let funded = Account::new(10 * LAMPORTS_PER_SOL, 0, &system_program::id());
+    let chain = start_chain_with(&owner, vec![(recipient.pubkey(), funded)]).await;
+    let app = get_test_app(chain.clone()).await;
+    let owner_bearer = sign_in(&app, &owner).await;
+    let recipient_bearer = sign_in(&app, &recipient).await;
+    let offer_address = |todo_id| {
+        let todo = pda::todo_address(&owner.pubkey(), todo_id, &solana_todo::ID).0;
+        pda::transfer_offer_address(&todo, &solana_todo::ID).0
+    };
+
+    for description in ["Offered then deleted", "Offered then offboarded"] {
+        let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/create", &create_request(description), &owner).await;
+        assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    }
+    for todo_id in [1, 2] {
+        let offer = OfferTransferTransactionRequest {
+            todo_id,
+            recipient: recipient.pubkey().to_string(),
+        };
+        let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/offer-transfer", &offer, &owner).await;
+        assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    }
+
+    // Deleting a todo closes its offer, and closing the profile closes the rest
+    let delete = DeleteTodoTransactionRequest { todo_id: 1, owner: None };
+    let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/delete", &delete, &owner).await;
+    assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    assert!(chain.get_account(offer_address(1)).await.unwrap().is_none());
+    assert!(chain.get_account(offer_address(2)).await.unwrap().is_some());
+
+    assert_eq!(close_profile(&app, &owner_bearer, &owner).await, 1);
+    assert!(chain.get_account(offer_address(2)).await.unwrap().is_none());
+
+    // Starting over puts new todos at the same addresses, which the old
+    // offers can no longer hand to the recipient
+    for description in ["Recreated #1", "Recreated #2"] {
+        let signed = prepare_and_sign(&app, &owner_bearer, "/api/transactions/prepare/create", &create_request(description), &owner).await;
+        assert_eq!(submit(&app, &owner_bearer, &signed).await.status(), StatusCode::OK);
+    }
+    for todo_id in [1, 2] {
+        let req = test::TestRequest::post()
+            .uri("/api/transactions/prepare/accept-transfer")
+            .append_header(recipient_bearer.clone())
+            .set_json(&AcceptTransferTransactionRequest {
+                owner: owner.pubkey().to_string(),
+                todo_id,
+            })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    let todos = list_todos(&app, &owner_bearer).await;
+    assert_eq!(todos.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 2]);
+    assert!(list_todos(&app, &recipient_bearer).await.is_empty());
+}
+
+// Simulate a signed transaction and decode the events in its logs
+async fn simulated_events(chain: &Arc<dyn ChainClient>, signed: &SignedTransaction) -> Vec<TodoEvent> {
+    let transaction: Transaction = bincode::deserialize(
+        &general_purpose::STANDARD
+            .decode(&signed.serialized_transaction)
+            .unwrap(),
+    )
+    .unwrap();
+    let simulation = chain.simulate_transaction(&transaction).await.unwrap();
+    assert!(simulation.error.is_none(), "simulation failed: {:?}", simulation.logs);
+    decode_events(&solana_todo::ID, &simulation.logs)
+}
+
+#[actix_rt::test]
+async fn test_simulated_logs_decode_into_program_events() {
+    init_test_env();
+    let wallet = Keypair::new();
+    let owner = wallet.pubkey();
+    let chain = start_chain(&wallet).await;
+    let app = get_test_app(chain.clone()).await;
+    let bearer = sign_in(&app, &wallet).await;
+    let clock: Clock = bincode::deserialize(&chain.get_account(sysvar::clock::id()).await.unwrap().unwrap().data).unwrap();
+    let timestamp = clock.unix_timestamp;
+
+    // A first create initializes the profile too, and both emit an event
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/create", &create_request("Decode me"), &wallet).await;
+    assert_eq!(
+        simulated_events(&chain, &signed).await,
+        vec![
+            TodoEvent::UserInitialized(UserInitialized { authority: owner, timestamp }),
+            TodoEvent::TodoCreated(TodoCreated {
+                id: 1,
+                owner,
+                description: "Decode me".to_string(),
+                due_date: 1625097600,
+                timestamp,
+            }),
+        ]
+    );
+    assert_eq!(submit(&app, &bearer, &signed).await.status(), StatusCode::OK);
+
+    let done = UpdateTodoRequest {
+        description: None,
+        status: None,
+        completed: Some(true),
+        due_date: None,
+        priority: None,
+        tags: None,
+    };
+    let signed = prepare_and_sign(&app, &bearer, "/api/transactions/prepare/update/1", &done, &wallet).await;
+    assert_eq!(
+        simulated_events(&chain, &signed).await,
+        vec![TodoEvent::TodoStatusChanged(TodoStatusChanged {
+            id: 1,
+            owner,
+            old_status: ProgramTodoStatus::Todo,
+            new_status: ProgramTodoStatus::Done,
+            timestamp,
+        })]
+    );
+}
@@ -1,5 +1,13 @@
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
-use std::str::FromStr;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+
+pub mod pda;
+pub mod versions;
+
+use pda::{DELEGATION_SEED, TODO_SEED, TRANSFER_SEED, USER_PROFILE_SEED};
 
 declare_id!("Ct2N3zw5LFiNj5mJ7hN2c4umze2pAWNjfYqazZHzDENy");
 
@@ -9,9 +17,16 @@ pub mod solana_todo {
 
     pub fn initialize_user(ctx: Context<InitializeUser>) -> Result<()> {
         let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.version = UserProfile::VERSION;
         user_profile.authority = ctx.accounts.authority.key();
         user_profile.todo_count = 0;
         user_profile.last_todo_id = 0;
+
+        emit!(UserInitialized {
+            authority: user_profile.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
@@ -21,66 +36,753 @@ pub mod solana_todo {
         due_date: i64,
     ) -> Result<()> {
         // Validate input
-        require!(description.len() <= 280, TodoError::DescriptionTooLong);
+        validate_description(&description)?;
         require!(due_date >= 0, TodoError::InvalidDueDate);
 
         let user_profile = &mut ctx.accounts.user_profile;
         let todo_account = &mut ctx.accounts.todo_account;
         let authority = &ctx.accounts.authority;
 
+        let now = Clock::get()?.unix_timestamp;
+
         // Generate a new task ID
         let todo_id = user_profile.last_todo_id + 1;
         user_profile.last_todo_id = todo_id;
         user_profile.todo_count += 1;
 
         // Initialize the new todo
-        todo_account.id = todo_id;
-        todo_account.description = description;
-        todo_account.completed = false;
-        todo_account.due_date = due_date;
-        todo_account.owner = authority.key();
-        todo_account.authority = authority.key();
+        todo_account.set_inner(TodoItem::new(todo_id, description, due_date, authority.key(), now));
+
+        emit!(TodoCreated {
+            id: todo_id,
+            owner: todo_account.owner,
+            description: todo_account.description.clone(),
+            due_date,
+            timestamp: now,
+        });
 
         Ok(())
     }
 
+    // Create several todos at once. Each todo's account is passed as a
+    // writable remaining account, in order, at the PDA of the id it gets:
+    // the ids follow on from the profile's counter, which is updated once.
+    pub fn create_todos_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateTodosBatch<'info>>,
+        todos: Vec<NewTodo>,
+    ) -> Result<()> {
+        require!(!todos.is_empty(), TodoError::EmptyBatch);
+        require!(
+            todos.len() == ctx.remaining_accounts.len(),
+            TodoError::BatchAccountsMismatch
+        );
+
+        let authority = &ctx.accounts.authority;
+        let first_todo_id = ctx.accounts.user_profile.last_todo_id + 1;
+        let now = Clock::get()?.unix_timestamp;
+
+        for ((todo_id, todo), todo_info) in (first_todo_id..).zip(todos).zip(ctx.remaining_accounts) {
+            // Validate input
+            validate_description(&todo.description)?;
+            require!(todo.due_date >= 0, TodoError::InvalidDueDate);
+
+            // The checks and account creation `init` gives create_todo
+            require!(todo_info.is_writable, ErrorCode::ConstraintMut);
+            let id_bytes = todo_id.to_le_bytes();
+            let (address, bump) = Pubkey::find_program_address(
+                &[TODO_SEED, authority.key.as_ref(), &id_bytes],
+                &crate::ID,
+            );
+            require_keys_eq!(todo_info.key(), address, ErrorCode::ConstraintSeeds);
+            create_account(
+                todo_info,
+                authority,
+                &ctx.accounts.system_program,
+                TodoItem::space(&todo.description),
+                &[TODO_SEED, authority.key.as_ref(), &id_bytes, &[bump]],
+            )?;
+
+            let item = TodoItem::new(todo_id, todo.description, todo.due_date, authority.key(), now);
+            item.try_serialize(&mut &mut todo_info.try_borrow_mut_data()?[..])?;
+
+            emit!(TodoCreated {
+                id: todo_id,
+                owner: item.owner,
+                description: item.description,
+                due_date: item.due_date,
+                timestamp: now,
+            });
+        }
+
+        let created = ctx.remaining_accounts.len() as u64;
+        let user_profile = &mut ctx.accounts.user_profile;
+        user_profile.last_todo_id += created;
+        user_profile.todo_count += created;
+
+        Ok(())
+    }
+
+    // Kept for clients predating statuses: completing moves the todo to Done,
+    // un-completing moves it back to Todo
     pub fn update_todo_status(
         ctx: Context<UpdateTodo>,
         completed: bool,
     ) -> Result<()> {
-        let todo_account = &mut ctx.accounts.todo_account;
-        
-        // Update completion status
-        todo_account.completed = completed;
-        
-        Ok(())
+        ctx.accounts.authorize(Some(Delegation::SET_STATUS))?;
+        let status = if completed { TodoStatus::Done } else { TodoStatus::Todo };
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.todo_account.set_status(status, now)
+    }
+
+    pub fn set_status(
+        ctx: Context<UpdateTodo>,
+        status: TodoStatus,
+    ) -> Result<()> {
+        ctx.accounts.authorize(Some(Delegation::SET_STATUS))?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.todo_account.set_status(status, now)
     }
 
     pub fn update_description(
         ctx: Context<UpdateTodo>,
         description: String,
     ) -> Result<()> {
-        // Validate input
-        require!(description.len() <= 280, TodoError::DescriptionTooLong);
-        
+        ctx.accounts.authorize(Some(Delegation::EDIT_DESCRIPTION))?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.todo_account.set_description(description, now)?;
+        ctx.accounts.fit_to_description()
+    }
+
+    pub fn update_due_date(
+        ctx: Context<UpdateTodo>,
+        due_date: i64,
+    ) -> Result<()> {
+        ctx.accounts.authorize(None)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.todo_account.set_due_date(due_date, now)
+    }
+
+    pub fn set_priority(
+        ctx: Context<UpdateTodo>,
+        priority: u8,
+    ) -> Result<()> {
+        ctx.accounts.authorize(None)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.todo_account.set_priority(priority, now)
+    }
+
+    // Replace the todo's tags with `tags`
+    pub fn set_tags(
+        ctx: Context<UpdateTodo>,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        ctx.accounts.authorize(None)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.todo_account.set_tags(tags, now)
+    }
+
+    // Change any combination of fields in one instruction; every field is
+    // validated before the todo is touched
+    pub fn update_todo(
+        ctx: Context<UpdateTodo>,
+        description: Option<String>,
+        status: Option<TodoStatus>,
+        due_date: Option<i64>,
+        priority: Option<u8>,
+        tags: Option<Vec<String>>,
+    ) -> Result<()> {
+        // Collaborators can only change what their permissions cover
+        let permissions = if due_date.is_some() || priority.is_some() || tags.is_some() {
+            None
+        } else {
+            let status_permission = status.map_or(0, |_| Delegation::SET_STATUS);
+            let description_permission = description.as_ref().map_or(0, |_| Delegation::EDIT_DESCRIPTION);
+            Some(status_permission | description_permission)
+        };
+        ctx.accounts.authorize(permissions)?;
+
         let todo_account = &mut ctx.accounts.todo_account;
-        
-        // Update description
-        todo_account.description = description;
-        
+
+        // Validate input
+        require!(
+            description.is_some()
+                || status.is_some()
+                || due_date.is_some()
+                || priority.is_some()
+                || tags.is_some(),
+            TodoError::NothingToUpdate
+        );
+        if let Some(description) = &description {
+            validate_description(description)?;
+        }
+        if let Some(due_date) = due_date {
+            require!(due_date >= 0, TodoError::InvalidDueDate);
+        }
+        if let Some(status) = status {
+            require!(
+                todo_account.status.can_transition_to(status),
+                TodoError::InvalidStatusTransition
+            );
+        }
+        if let Some(priority) = priority {
+            require!(priority <= TodoItem::MAX_PRIORITY, TodoError::InvalidPriority);
+        }
+        if let Some(tags) = &tags {
+            validate_tags(tags)?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let resize = description.is_some();
+
+        if let Some(description) = description {
+            todo_account.set_description(description, now)?;
+        }
+        if let Some(due_date) = due_date {
+            todo_account.set_due_date(due_date, now)?;
+        }
+        if let Some(status) = status {
+            todo_account.set_status(status, now)?;
+        }
+        if let Some(priority) = priority {
+            todo_account.set_priority(priority, now)?;
+        }
+        if let Some(tags) = tags {
+            todo_account.set_tags(tags, now)?;
+        }
+
+        if resize {
+            ctx.accounts.fit_to_description()?;
+        }
+
         Ok(())
     }
 
-    pub fn delete_todo(ctx: Context<DeleteTodo>) -> Result<()> {
+    // Close a todo and return its rent to the owner. A pending transfer
+    // offer for the todo can be passed as a writable remaining account to
+    // close it as well, so it cannot outlive the todo.
+    pub fn delete_todo<'info>(ctx: Context<'_, '_, '_, 'info, DeleteTodo<'info>>) -> Result<()> {
+        authorize(
+            &ctx.accounts.todo_account,
+            ctx.accounts.authority.key,
+            ctx.accounts.delegation.as_deref(),
+            Some(Delegation::DELETE),
+        )?;
+        let owner = ctx.accounts.owner.to_account_info();
+        for offer_info in ctx.remaining_accounts {
+            let offer = load_remaining_offer(offer_info, owner.key)?;
+            require_keys_eq!(offer.todo, ctx.accounts.todo_account.key(), ErrorCode::ConstraintSeeds);
+            close_offer(offer_info, &offer, &owner)?;
+        }
         let user_profile = &mut ctx.accounts.user_profile;
         
-        // Update user profile count
-        user_profile.todo_count = user_profile.todo_count.checked_sub(1).unwrap_or(0);
-        
-        // The account will be closed and lamports returned to the authority
+        // Update the owner's profile count
+        user_profile.todo_count = user_profile.todo_count.saturating_sub(1);
         
+        // The account will be closed and lamports returned to the owner
+        let todo_account = &ctx.accounts.todo_account;
+        emit!(TodoDeleted {
+            id: todo_account.id,
+            owner: todo_account.owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
+
+    // Close every todo passed as a writable remaining account, each of which
+    // must be completed, and return their rent to the authority
+    pub fn delete_completed_todos<'info>(
+        ctx: Context<'_, '_, '_, 'info, DeleteCompletedTodos<'info>>,
+    ) -> Result<()> {
+        let authority = ctx.accounts.authority.to_account_info();
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        let mut deleted_todo_ids = Vec::with_capacity(ctx.remaining_accounts.len());
+        for todo_info in ctx.remaining_accounts {
+            let todo = load_remaining_todo(todo_info, authority.key)?;
+            require!(todo.completed(), TodoError::TodoNotCompleted);
+            close_account(todo_info, &authority)?;
+            user_profile.todo_count = user_profile.todo_count.saturating_sub(1);
+            deleted_todo_ids.push(todo.id);
+        }
+
+        emit!(CompletedTodosDeleted {
+            owner: authority.key(),
+            deleted_todo_ids,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Close every todo passed as a writable remaining account, open or not,
+    // and return their rent to the authority. Transfer offers the authority
+    // made can be passed too and are closed along with their todos. This
+    // lets an offboarding with more todos than close_user takes in one call
+    // be split across several transactions.
+    pub fn delete_todos<'info>(ctx: Context<'_, '_, '_, 'info, DeleteTodos<'info>>) -> Result<()> {
+        let authority = ctx.accounts.authority.to_account_info();
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        let mut deleted_todo_ids = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts {
+            if let Some(todo) = close_remaining_todo_or_offer(info, &authority)? {
+                user_profile.todo_count = user_profile.todo_count.saturating_sub(1);
+                deleted_todo_ids.push(todo.id);
+            }
+        }
+
+        emit!(TodosDeleted {
+            owner: authority.key(),
+            deleted_todo_ids,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Close the authority's profile and return its rent. Todos still open,
+    // transfer offers the authority made and delegations they granted can be
+    // passed as writable remaining accounts to close them in the same call;
+    // the profile only closes once no todos are left.
+    pub fn close_user<'info>(ctx: Context<'_, '_, '_, 'info, CloseUser<'info>>) -> Result<()> {
+        let authority = ctx.accounts.authority.to_account_info();
+
+        let mut todo_count = ctx.accounts.user_profile.todo_count;
+        let mut closed_todo_ids = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts {
+            // Delegations would otherwise carry over to a profile opened later
+            if info.try_borrow_data()?.starts_with(&Delegation::DISCRIMINATOR) {
+                let delegation = load_remaining_delegation(info, authority.key)?;
+                close_account(info, &authority)?;
+                emit!(DelegationRevoked {
+                    owner: delegation.owner,
+                    delegate: delegation.delegate,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                continue;
+            }
+            if let Some(todo) = close_remaining_todo_or_offer(info, &authority)? {
+                todo_count = todo_count.saturating_sub(1);
+                closed_todo_ids.push(todo.id);
+            }
+        }
+        require!(todo_count == 0, TodoError::ProfileHasTodos);
+
+        // The profile itself is closed by the `close = authority` constraint
+        emit!(UserClosed {
+            authority: authority.key(),
+            closed_todo_ids,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Let `delegate` make the changes `permissions` cover to any of the
+    // owner's todos
+    pub fn grant_delegation(ctx: Context<GrantDelegation>, permissions: u8) -> Result<()> {
+        validate_permissions(permissions)?;
+        let owner = ctx.accounts.owner.key();
+        let delegate = ctx.accounts.delegate.key();
+        require_keys_neq!(owner, delegate, TodoError::InvalidDelegate);
+
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.delegation.set_inner(Delegation {
+            version: Delegation::VERSION,
+            owner,
+            delegate,
+            permissions,
+            created_at: now,
+            updated_at: now,
+        });
+
+        emit!(DelegationGranted {
+            owner,
+            delegate,
+            permissions,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Replace the permissions of an existing delegation
+    pub fn update_delegation(ctx: Context<UpdateDelegation>, permissions: u8) -> Result<()> {
+        validate_permissions(permissions)?;
+        let delegation = &mut ctx.accounts.delegation;
+        let now = Clock::get()?.unix_timestamp;
+
+        let old_permissions = std::mem::replace(&mut delegation.permissions, permissions);
+        delegation.updated_at = now;
+
+        emit!(DelegationUpdated {
+            owner: delegation.owner,
+            delegate: delegation.delegate,
+            old_permissions,
+            new_permissions: permissions,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Withdraw a delegation and return its rent to the owner
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        let delegation = &ctx.accounts.delegation;
+        emit!(DelegationRevoked {
+            owner: delegation.owner,
+            delegate: delegation.delegate,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Offer one of the owner's todos to `recipient`, who takes it over with
+    // transfer_todo. A todo has at most one pending offer.
+    pub fn offer_transfer(ctx: Context<OfferTransfer>) -> Result<()> {
+        let owner = ctx.accounts.authority.key();
+        let recipient = ctx.accounts.recipient.key();
+        require_keys_neq!(owner, recipient, TodoError::InvalidRecipient);
+
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.transfer_offer.set_inner(TransferOffer {
+            version: TransferOffer::VERSION,
+            todo: ctx.accounts.todo_account.key(),
+            todo_id: ctx.accounts.todo_account.id,
+            todo_created_at: ctx.accounts.todo_account.created_at,
+            owner,
+            recipient,
+            created_at: now,
+        });
+
+        emit!(TodoTransferOffered {
+            id: ctx.accounts.todo_account.id,
+            owner,
+            recipient,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Accept a transfer offer. Todo accounts are derived from their owner
+    // and id, so the todo moves to a new account under the recipient with
+    // the next id from their profile, keeping its description, status,
+    // dates, priority and tags. The old account and the offer are closed,
+    // returning their rent to the previous owner.
+    pub fn transfer_todo(ctx: Context<TransferTodo>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let recipient = ctx.accounts.recipient.key();
+        let todo = &ctx.accounts.todo_account;
+
+        let owner_profile = &mut ctx.accounts.owner_profile;
+        owner_profile.todo_count = owner_profile.todo_count.saturating_sub(1);
+
+        let recipient_profile = &mut ctx.accounts.recipient_profile;
+        let new_id = recipient_profile.last_todo_id + 1;
+        recipient_profile.last_todo_id = new_id;
+        recipient_profile.todo_count += 1;
+
+        ctx.accounts.new_todo_account.set_inner(TodoItem {
+            id: new_id,
+            owner: recipient,
+            authority: recipient,
+            updated_at: now,
+            ..TodoItem::clone(todo)
+        });
+
+        emit!(TodoTransferred {
+            old_id: todo.id,
+            new_id,
+            from: todo.owner,
+            to: recipient,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Withdraw a pending offer, either by the owner or by the recipient
+    // declining it, and return its rent to the owner
+    pub fn cancel_transfer(ctx: Context<CancelTransfer>) -> Result<()> {
+        let offer = &ctx.accounts.transfer_offer;
+        let authority = ctx.accounts.authority.key();
+        require!(
+            authority == offer.owner || authority == offer.recipient,
+            TodoError::UnauthorizedAccess
+        );
+
+        emit!(TodoTransferCancelled {
+            todo: offer.todo,
+            owner: offer.owner,
+            recipient: offer.recipient,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Upgrade a todo account stored in an earlier layout version to the
+    // current one, sized to its description. Fields the old layout lacked keep
+    // their defaults: no recorded times, priority 0 and no tags.
+    pub fn migrate_todo(ctx: Context<MigrateTodo>) -> Result<()> {
+        let todo_info = ctx.accounts.todo_account.to_account_info();
+        let authority = &ctx.accounts.authority;
+
+        let (from_version, todo) = {
+            let data = todo_info.try_borrow_data()?;
+            let version = TodoItem::stored_version(&data)
+                .ok_or_else(|| error!(ErrorCode::AccountDiscriminatorMismatch))?;
+            require!(version != TodoItem::VERSION, TodoError::AlreadyMigrated);
+            (version, TodoItem::try_deserialize_any_version(&data)?)
+        };
+        require_keys_eq!(todo.authority, authority.key(), ErrorCode::ConstraintHasOne);
+        require_keys_eq!(todo.owner, authority.key(), TodoError::UnauthorizedAccess);
+
+        let space = TodoItem::space(&todo.description);
+        resize_account(&todo_info, authority, &authority.to_account_info(), &ctx.accounts.system_program, space)?;
+        let mut data = todo_info.try_borrow_mut_data()?;
+        todo.try_serialize(&mut &mut data[..])?;
+
+        emit!(TodoMigrated {
+            id: todo.id,
+            owner: todo.owner,
+            from_version,
+            to_version: TodoItem::VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Upgrade a profile account stored in an earlier layout version to the
+    // current one, growing it as needed
+    pub fn migrate_profile(ctx: Context<MigrateProfile>) -> Result<()> {
+        let profile_info = ctx.accounts.user_profile.to_account_info();
+        let authority = &ctx.accounts.authority;
+
+        let (from_version, profile) = {
+            let data = profile_info.try_borrow_data()?;
+            let version = UserProfile::stored_version(&data)
+                .ok_or_else(|| error!(ErrorCode::AccountDiscriminatorMismatch))?;
+            require!(version != UserProfile::VERSION, TodoError::AlreadyMigrated);
+            (version, UserProfile::try_deserialize_any_version(&data)?)
+        };
+        require_keys_eq!(profile.authority, authority.key(), ErrorCode::ConstraintHasOne);
+
+        resize_account(
+            &profile_info,
+            authority,
+            &authority.to_account_info(),
+            &ctx.accounts.system_program,
+            UserProfile::SPACE,
+        )?;
+        let mut data = profile_info.try_borrow_mut_data()?;
+        profile.try_serialize(&mut &mut data[..])?;
+
+        emit!(UserProfileMigrated {
+            authority: profile.authority,
+            from_version,
+            to_version: UserProfile::VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Check that `signer` may change `todo`. The owner can make any change; a
+// collaborator needs a delegation granting every permission in
+// `permissions`, and None marks changes that are the owner's alone.
+fn authorize(
+    todo: &TodoItem,
+    signer: &Pubkey,
+    delegation: Option<&Delegation>,
+    permissions: Option<u8>,
+) -> Result<()> {
+    match delegation {
+        // The delegation's seeds tie it to the todo's owner and the signer
+        Some(delegation) => require!(
+            matches!(permissions, Some(permissions) if delegation.allows(permissions)),
+            TodoError::MissingPermission
+        ),
+        None => {
+            require_keys_eq!(todo.authority, *signer, ErrorCode::ConstraintHasOne);
+            require_keys_eq!(todo.owner, *signer, TodoError::UnauthorizedAccess);
+        }
+    }
+    Ok(())
+}
+
+// Resize a program-owned account to `space` bytes, keeping it exactly rent
+// exempt: `payer` tops up the balance when it grows and `refund_to` gets the
+// excess back when it shrinks
+fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    refund_to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let balance = account.lamports();
+    if balance > required {
+        // The program owns the account, so it can move lamports out directly
+        **account.try_borrow_mut_lamports()? -= balance - required;
+        **refund_to.try_borrow_mut_lamports()? += balance - required;
+    } else if balance < required {
+        let shortfall = required - balance;
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(space, true)?;
+
+    Ok(())
+}
+
+// Create a program-owned account at a PDA the way the `init` constraint
+// does, which also works when the address was already sent lamports
+fn create_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let balance = account.lamports();
+    let signer_seeds = &[seeds];
+
+    if balance == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            required,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    if balance < required {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            required - balance,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            signer_seeds,
+        ),
+        &crate::ID,
+    )
+}
+
+// Decode a todo passed through remaining accounts, with the checks the
+// declared todo accounts get from their constraints. Any layout version is
+// accepted since the todo is only read before being closed.
+fn load_remaining_todo(info: &AccountInfo, authority: &Pubkey) -> Result<TodoItem> {
+    require!(info.is_writable, ErrorCode::ConstraintMut);
+    require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    let todo = TodoItem::try_deserialize_any_version(&info.try_borrow_data()?)?;
+    require_keys_eq!(todo.authority, *authority, ErrorCode::ConstraintHasOne);
+    require_keys_eq!(todo.owner, *authority, TodoError::UnauthorizedAccess);
+    Ok(todo)
+}
+
+// Decode a transfer offer passed through remaining accounts, checking it
+// was made by `owner`
+fn load_remaining_offer(info: &AccountInfo, owner: &Pubkey) -> Result<TransferOffer> {
+    require!(info.is_writable, ErrorCode::ConstraintMut);
+    require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    let offer = TransferOffer::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require_keys_eq!(offer.owner, *owner, ErrorCode::ConstraintHasOne);
+    Ok(offer)
+}
+
+// Close a todo or a transfer offer of `authority` passed through remaining
+// accounts, returning the todo when it was one
+fn close_remaining_todo_or_offer<'info>(
+    info: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+) -> Result<Option<TodoItem>> {
+    // Offers the authority made are closed along with their todos
+    if info.try_borrow_data()?.starts_with(&TransferOffer::DISCRIMINATOR) {
+        let offer = load_remaining_offer(info, authority.key)?;
+        close_offer(info, &offer, authority)?;
+        return Ok(None);
+    }
+    let todo = load_remaining_todo(info, authority.key)?;
+    close_account(info, authority)?;
+    Ok(Some(todo))
+}
+
+// Decode a delegation passed through remaining accounts, checking it was
+// granted by `owner`
+fn load_remaining_delegation(info: &AccountInfo, owner: &Pubkey) -> Result<Delegation> {
+    require!(info.is_writable, ErrorCode::ConstraintMut);
+    require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    let delegation = Delegation::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require_keys_eq!(delegation.owner, *owner, ErrorCode::ConstraintHasOne);
+    Ok(delegation)
+}
+
+// Close a transfer offer whose todo is going away, returning its rent to the
+// owner, and report it as cancelled
+fn close_offer<'info>(info: &AccountInfo<'info>, offer: &TransferOffer, owner: &AccountInfo<'info>) -> Result<()> {
+    close_account(info, owner)?;
+    emit!(TodoTransferCancelled {
+        todo: offer.todo,
+        owner: offer.owner,
+        recipient: offer.recipient,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Close a program-owned account the way the `close` constraint does: move
+// its lamports to `destination` and hand it back to the system program
+fn close_account<'info>(account: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    **destination.try_borrow_mut_lamports()? += account.lamports();
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&system_program::ID);
+    account.realloc(0, false)?;
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -88,8 +790,8 @@ pub struct InitializeUser<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8 + 8, // discriminator + pubkey + todo_count + last_todo_id
-        seeds = [b"user-profile", authority.key().as_ref()],
+        space = UserProfile::SPACE,
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
         bump
     )]
     pub user_profile: Account<'info, UserProfile>,
@@ -100,10 +802,11 @@ pub struct InitializeUser<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(description: String)]
 pub struct CreateTodo<'info> {
     #[account(
         mut,
-        seeds = [b"user-profile", authority.key().as_ref()],
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
         bump,
         has_one = authority,
     )]
@@ -112,8 +815,8 @@ pub struct CreateTodo<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 8 + 280 + 1 + 8 + 32 + 32, // discriminator + id + description + completed + due_date + owner + authority
-        seeds = [b"todo", authority.key().as_ref(), &user_profile.last_todo_id.checked_add(1).unwrap().to_le_bytes()],
+        space = TodoItem::space(&description),
+        seeds = [TODO_SEED, authority.key().as_ref(), &user_profile.last_todo_id.checked_add(1).unwrap().to_le_bytes()],
         bump
     )]
     pub todo_account: Account<'info, TodoItem>,
@@ -123,55 +826,843 @@ pub struct CreateTodo<'info> {
     pub system_program: Program<'info, System>,
 }
 
+// The todos to create come after these accounts, see `create_todos_batch`
 #[derive(Accounts)]
-pub struct UpdateTodo<'info> {
+pub struct CreateTodosBatch<'info> {
     #[account(
         mut,
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
+        bump,
         has_one = authority,
-        constraint = todo_account.owner == authority.key() @ TodoError::UnauthorizedAccess
     )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Signed by the todo's owner, or by a collaborator passing their delegation.
+// Handlers check the signer's permissions with `authorize`.
+#[derive(Accounts)]
+pub struct UpdateTodo<'info> {
+    #[account(mut, has_one = owner @ TodoError::UnauthorizedAccess)]
     pub todo_account: Account<'info, TodoItem>,
     
+    // Pays the extra rent when a new description grows the todo
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    // Refunded the rent a shorter description frees, whoever signed
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [DELEGATION_SEED, todo_account.owner.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
 }
 
+impl<'info> UpdateTodo<'info> {
+    fn authorize(&self, permissions: Option<u8>) -> Result<()> {
+        authorize(&self.todo_account, self.authority.key, self.delegation.as_deref(), permissions)
+    }
+
+    // Resize the todo account to fit its description
+    fn fit_to_description(&self) -> Result<()> {
+        resize_account(
+            &self.todo_account.to_account_info(),
+            &self.authority,
+            &self.owner.to_account_info(),
+            &self.system_program,
+            TodoItem::space(&self.todo_account.description),
+        )
+    }
+}
+
+// Signed by the todo's owner, or by a collaborator passing their delegation;
+// either way the owner's profile is updated and the rent goes to the owner
 #[derive(Accounts)]
 pub struct DeleteTodo<'info> {
     #[account(
         mut,
-        seeds = [b"user-profile", authority.key().as_ref()],
+        seeds = [USER_PROFILE_SEED, todo_account.owner.as_ref()],
         bump,
-        has_one = authority,
     )]
     pub user_profile: Account<'info, UserProfile>,
     
+    #[account(mut, close = owner)]
+    pub todo_account: Account<'info, TodoItem>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut, address = todo_account.owner @ TodoError::UnauthorizedAccess)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        seeds = [DELEGATION_SEED, todo_account.owner.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
+}
+
+// The todos to delete come after these accounts, see `delete_completed_todos`
+#[derive(Accounts)]
+pub struct DeleteCompletedTodos<'info> {
+    #[account(
+        mut,
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
+        bump,
+        has_one = authority,
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+// The todos to delete come after these accounts, see `delete_todos`
+#[derive(Accounts)]
+pub struct DeleteTodos<'info> {
+    #[account(
+        mut,
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
+        bump,
+        has_one = authority,
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+// The todos to close come after these accounts, see `close_user`
+#[derive(Accounts)]
+pub struct CloseUser<'info> {
     #[account(
         mut,
         close = authority,
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
+        bump,
+        has_one = authority,
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrantDelegation<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = Delegation::SPACE,
+        seeds = [DELEGATION_SEED, owner.key().as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: any wallet can be a collaborator; only its address is used
+    pub delegate: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDelegation<'info> {
+    #[account(
+        mut,
+        seeds = [DELEGATION_SEED, owner.key().as_ref(), delegation.delegate.as_ref()],
+        bump,
+        has_one = owner,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [DELEGATION_SEED, owner.key().as_ref(), delegation.delegate.as_ref()],
+        bump,
+        has_one = owner,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OfferTransfer<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = TransferOffer::SPACE,
+        seeds = [TRANSFER_SEED, todo_account.key().as_ref()],
+        bump
+    )]
+    pub transfer_offer: Account<'info, TransferOffer>,
+
+    #[account(
         has_one = authority,
-        constraint = todo_account.owner == authority.key() @ TodoError::UnauthorizedAccess
+        constraint = todo_account.owner == authority.key() @ TodoError::UnauthorizedAccess,
     )]
     pub todo_account: Account<'info, TodoItem>,
-    
+
+    /// CHECK: any wallet can receive a todo; only its address is used
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Signed by the recipient. The new todo account is at the PDA of the next
+// id in the recipient's profile.
+#[derive(Accounts)]
+pub struct TransferTodo<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [TRANSFER_SEED, todo_account.key().as_ref()],
+        bump,
+        has_one = owner,
+        has_one = recipient @ TodoError::NotTransferRecipient,
+        // Todo addresses come back when a closed profile starts over, so the
+        // offer must still be for the todo it was made for
+        constraint = transfer_offer.todo_id == todo_account.id
+            && transfer_offer.todo_created_at == todo_account.created_at @ TodoError::StaleTransferOffer,
+    )]
+    pub transfer_offer: Account<'info, TransferOffer>,
+
+    #[account(mut, close = owner, has_one = owner @ TodoError::UnauthorizedAccess)]
+    pub todo_account: Account<'info, TodoItem>,
+
+    #[account(
+        mut,
+        seeds = [USER_PROFILE_SEED, owner.key().as_ref()],
+        bump,
+    )]
+    pub owner_profile: Account<'info, UserProfile>,
+
     #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [USER_PROFILE_SEED, recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_profile: Account<'info, UserProfile>,
+
+    #[account(
+        init,
+        payer = recipient,
+        space = TodoItem::space(&todo_account.description),
+        seeds = [TODO_SEED, recipient.key().as_ref(), &recipient_profile.last_todo_id.checked_add(1).unwrap().to_le_bytes()],
+        bump
+    )]
+    pub new_todo_account: Account<'info, TodoItem>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Signed by the offer's owner or recipient, see `cancel_transfer`
+#[derive(Accounts)]
+pub struct CancelTransfer<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [TRANSFER_SEED, transfer_offer.todo.as_ref()],
+        bump,
+        has_one = owner,
+    )]
+    pub transfer_offer: Account<'info, TransferOffer>,
+
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateTodo<'info> {
+    /// CHECK: may still be in the legacy layout, so it is decoded and its
+    /// authority checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub todo_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProfile<'info> {
+    /// CHECK: may still be in an earlier layout, so it is decoded and its
+    /// authority checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [USER_PROFILE_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub user_profile: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 pub struct UserProfile {
+    // Layout version, see `versions`
+    pub version: u8,
     pub authority: Pubkey,
     pub todo_count: u64,
     pub last_todo_id: u64,
 }
 
+impl UserProfile {
+    pub const VERSION: u8 = 1;
+
+    // discriminator + version + authority + todo_count + last_todo_id
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + 8;
+}
+
+// Lets `delegate` work on `owner`'s todos within `permissions`
 #[account]
+pub struct Delegation {
+    // Layout version, so later layouts can be told apart
+    pub version: u8,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    // A combination of the permission flags below
+    pub permissions: u8,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Delegation {
+    pub const VERSION: u8 = 1;
+
+    // Permission flags: changing a todo's status, editing its description
+    // and deleting it. Due dates, priorities and tags stay with the owner.
+    pub const SET_STATUS: u8 = 1 << 0;
+    pub const EDIT_DESCRIPTION: u8 = 1 << 1;
+    pub const DELETE: u8 = 1 << 2;
+    pub const ALL_PERMISSIONS: u8 = Self::SET_STATUS | Self::EDIT_DESCRIPTION | Self::DELETE;
+
+    // discriminator + version + owner + delegate + permissions + created_at + updated_at
+    pub const SPACE: usize = 8 + 1 + 32 + 32 + 1 + 8 + 8;
+
+    // Where the owner starts, for finding a wallet's delegations by memcmp
+    pub const OWNER_OFFSET: usize = 8 + 1;
+
+    // Whether the delegation grants every permission in `permissions`
+    pub fn allows(&self, permissions: u8) -> bool {
+        self.permissions & permissions == permissions
+    }
+}
+
+// A pending offer of the `todo` account from `owner` to `recipient`. The
+// todo's id and creation time tell it apart from a later todo at the same
+// address.
+#[account]
+pub struct TransferOffer {
+    // Layout version, so later layouts can be told apart
+    pub version: u8,
+    pub todo: Pubkey,
+    pub todo_id: u64,
+    pub todo_created_at: i64,
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub created_at: i64,
+}
+
+impl TransferOffer {
+    pub const VERSION: u8 = 1;
+
+    // discriminator + version + todo + todo_id + todo_created_at + owner
+    // + recipient + created_at
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + 8 + 32 + 32 + 8;
+
+    // Where the owner starts, for finding a wallet's offers by memcmp
+    pub const OWNER_OFFSET: usize = 8 + 1 + 32 + 8 + 8;
+}
+
+// Not #[account]: the trait impls below check the layout version before
+// decoding, so instructions given a todo still in an older layout fail with
+// AccountNeedsMigration rather than a generic deserialization error
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct TodoItem {
+    // Layout version, see `versions`
+    pub version: u8,
+    // Kept ahead of the variable-length description so a wallet's todos can
+    // be found by memcmp at OWNER_OFFSET
+    pub owner: Pubkey,
     pub id: u64,
     pub description: String,
-    pub completed: bool,
+    pub status: TodoStatus,
     pub due_date: i64,
+    pub authority: Pubkey,
+    // Unix timestamps from the Clock sysvar; 0 means the todo predates
+    // timestamp tracking and the time was never recorded
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+    // 0 (none) up to MAX_PRIORITY (highest)
+    pub priority: u8,
+    pub tags: Vec<String>,
+}
+
+// sha256("account:TodoItem")[..8], as #[account] would derive it
+impl Discriminator for TodoItem {
+    const DISCRIMINATOR: [u8; 8] = [165, 128, 132, 139, 204, 211, 132, 83];
+}
+
+impl Owner for TodoItem {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl AccountSerialize for TodoItem {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer
+            .write_all(&Self::DISCRIMINATOR)
+            .map_err(|_| error!(ErrorCode::AccountDidNotSerialize))?;
+        AnchorSerialize::serialize(self, writer).map_err(|_| error!(ErrorCode::AccountDidNotSerialize))
+    }
+}
+
+impl AccountDeserialize for TodoItem {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        require!(buf.len() >= 8, ErrorCode::AccountDiscriminatorNotFound);
+        require!(
+            buf.starts_with(&Self::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        match Self::stored_version(buf) {
+            Some(Self::VERSION) => Self::try_deserialize_unchecked(buf),
+            Some(version) if version < Self::VERSION => err!(TodoError::AccountNeedsMigration),
+            _ => err!(TodoError::UnsupportedVersion),
+        }
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data).map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
+impl TodoItem {
+    pub const VERSION: u8 = 4;
+    pub const MAX_PRIORITY: u8 = 3;
+    pub const MAX_TAGS: usize = 5;
+    pub const MAX_TAG_LEN: usize = 20;
+    pub const MAX_DESCRIPTION_CHARS: usize = 280;
+
+    // discriminator + version + owner + id + description length prefix
+    // + status + due_date + authority + created_at + updated_at + completed_at
+    // + priority + tags (length prefix + MAX_TAGS length-prefixed tags)
+    pub const BASE_SPACE: usize = 8 + 1 + 32 + 8 + 4 + 1 + 8 + 32 + 8 + 8 + 1 + 8 + 1
+        + 4 + Self::MAX_TAGS * (4 + Self::MAX_TAG_LEN);
+
+    // Where the owner starts, for finding a wallet's todos by memcmp
+    pub const OWNER_OFFSET: usize = 8 + 1;
+
+    // Accounts are sized to their description's UTF-8 bytes, so rent is only
+    // paid for what the description uses
+    pub fn space(description: &str) -> usize {
+        Self::BASE_SPACE + description.len()
+    }
+
+    // A new todo, not yet started, with no priority or tags
+    fn new(id: u64, description: String, due_date: i64, authority: Pubkey, now: i64) -> Self {
+        Self {
+            version: Self::VERSION,
+            owner: authority,
+            id,
+            description,
+            status: TodoStatus::Todo,
+            due_date,
+            authority,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+            priority: 0,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn completed(&self) -> bool {
+        self.status.is_completed()
+    }
+
+    // Move to `status` if the workflow allows it, keeping the original
+    // completion time if the todo was already done
+    fn set_status(&mut self, status: TodoStatus, now: i64) -> Result<()> {
+        require!(
+            self.status.can_transition_to(status),
+            TodoError::InvalidStatusTransition
+        );
+
+        let old_status = self.status;
+        self.status = status;
+        self.updated_at = now;
+        if !status.is_completed() {
+            self.completed_at = None;
+        } else if !old_status.is_completed() {
+            self.completed_at = Some(now);
+        }
+
+        emit!(TodoStatusChanged {
+            id: self.id,
+            owner: self.owner,
+            old_status,
+            new_status: status,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // The caller resizes the account to fit the new description
+    fn set_description(&mut self, description: String, now: i64) -> Result<()> {
+        // Validate input
+        validate_description(&description)?;
+
+        let old_description = std::mem::replace(&mut self.description, description);
+        self.updated_at = now;
+
+        emit!(TodoDescriptionUpdated {
+            id: self.id,
+            owner: self.owner,
+            old_description,
+            new_description: self.description.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    fn set_due_date(&mut self, due_date: i64, now: i64) -> Result<()> {
+        // Validate input
+        require!(due_date >= 0, TodoError::InvalidDueDate);
+
+        let old_due_date = self.due_date;
+        self.due_date = due_date;
+        self.updated_at = now;
+
+        emit!(TodoDueDateUpdated {
+            id: self.id,
+            owner: self.owner,
+            old_due_date,
+            new_due_date: due_date,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    fn set_priority(&mut self, priority: u8, now: i64) -> Result<()> {
+        // Validate input
+        require!(priority <= Self::MAX_PRIORITY, TodoError::InvalidPriority);
+
+        let old_priority = self.priority;
+        self.priority = priority;
+        self.updated_at = now;
+
+        emit!(TodoPriorityUpdated {
+            id: self.id,
+            owner: self.owner,
+            old_priority,
+            new_priority: priority,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    fn set_tags(&mut self, tags: Vec<String>, now: i64) -> Result<()> {
+        // Validate input
+        validate_tags(&tags)?;
+
+        let old_tags = std::mem::replace(&mut self.tags, tags);
+        self.updated_at = now;
+
+        emit!(TodoTagsUpdated {
+            id: self.id,
+            owner: self.owner,
+            old_tags,
+            new_tags: self.tags.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+// Descriptions are limited in characters rather than bytes, so non-ASCII
+// text gets the same allowance
+fn validate_description(description: &str) -> Result<()> {
+    require!(
+        description.chars().count() <= TodoItem::MAX_DESCRIPTION_CHARS,
+        TodoError::DescriptionTooLong
+    );
+    Ok(())
+}
+
+// At most MAX_TAGS tags, each non-empty, no longer than MAX_TAG_LEN UTF-8
+// bytes and different from the others. Unlike descriptions, tags are limited
+// in bytes: every todo reserves MAX_TAG_LEN bytes of space per tag.
+fn validate_tags(tags: &[String]) -> Result<()> {
+    require!(tags.len() <= TodoItem::MAX_TAGS, TodoError::TooManyTags);
+    for (index, tag) in tags.iter().enumerate() {
+        require!(
+            !tag.is_empty() && tag.len() <= TodoItem::MAX_TAG_LEN && !tags[..index].contains(tag),
+            TodoError::InvalidTag
+        );
+    }
+    Ok(())
+}
+
+// One todo to create with `create_todos_batch`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NewTodo {
+    pub description: String,
+    pub due_date: i64,
+}
+
+// A non-empty combination of Delegation's permission flags
+fn validate_permissions(permissions: u8) -> Result<()> {
+    require!(
+        permissions != 0 && permissions & !Delegation::ALL_PERMISSIONS == 0,
+        TodoError::InvalidPermissions
+    );
+    Ok(())
+}
+
+// Where a todo is in its workflow. Done is the second variant on purpose:
+// todos stored a `completed` bool in this byte before statuses existed, and
+// Borsh encodes a bool and an enum variant the same way, so false and true
+// read back as Todo and Done without rewriting any account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TodoStatus {
+    Todo,
+    Done,
+    InProgress,
+    Blocked,
+    Cancelled,
+}
+
+impl TodoStatus {
+    pub fn is_completed(self) -> bool {
+        self == TodoStatus::Done
+    }
+
+    // Allowed workflow moves. Staying in the same status is always allowed;
+    // a blocked todo has to be unblocked before it can be finished, and
+    // finished or cancelled todos can only be reopened.
+    pub fn can_transition_to(self, next: TodoStatus) -> bool {
+        use TodoStatus::*;
+
+        self == next
+            || matches!(
+                (self, next),
+                (Todo, InProgress | Blocked | Done | Cancelled)
+                    | (InProgress, Todo | Blocked | Done | Cancelled)
+                    | (Blocked, Todo | InProgress | Cancelled)
+                    | (Done, Todo | InProgress)
+                    | (Cancelled, Todo)
+            )
+    }
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserInitialized {
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoCreated {
+    pub id: u64,
     pub owner: Pubkey,
+    pub description: String,
+    pub due_date: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoStatusChanged {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub old_status: TodoStatus,
+    pub new_status: TodoStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoDescriptionUpdated {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub old_description: String,
+    pub new_description: String,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoDueDateUpdated {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub old_due_date: i64,
+    pub new_due_date: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoPriorityUpdated {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub old_priority: u8,
+    pub new_priority: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoTagsUpdated {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub old_tags: Vec<String>,
+    pub new_tags: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoDeleted {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedTodosDeleted {
+    pub owner: Pubkey,
+    pub deleted_todo_ids: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodosDeleted {
+    pub owner: Pubkey,
+    pub deleted_todo_ids: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserClosed {
+    pub authority: Pubkey,
+    // Todos closed along with the profile
+    pub closed_todo_ids: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationGranted {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub permissions: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationUpdated {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub old_permissions: u8,
+    pub new_permissions: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationRevoked {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoTransferOffered {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoTransferred {
+    pub old_id: u64,
+    pub new_id: u64,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoTransferCancelled {
+    pub todo: Pubkey,
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoMigrated {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserProfileMigrated {
     pub authority: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
 }
 
 #[error_code]
@@ -182,4 +1673,67 @@ pub enum TodoError {
     InvalidDueDate,
     #[msg("Only the owner can modify this todo item")]
     UnauthorizedAccess,
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("At least one field must be updated")]
+    NothingToUpdate,
+    #[msg("The todo cannot move to that status from its current one")]
+    InvalidStatusTransition,
+    #[msg("Priority must be between 0 and 3")]
+    InvalidPriority,
+    #[msg("A todo can have at most 5 tags")]
+    TooManyTags,
+    #[msg("Tags must be unique and between 1 and 20 bytes long")]
+    InvalidTag,
+    #[msg("Account layout version is not supported by this program")]
+    UnsupportedVersion,
+    #[msg("Every remaining todo must be closed with the profile")]
+    ProfileHasTodos,
+    #[msg("Only completed todos can be deleted in bulk")]
+    TodoNotCompleted,
+    #[msg("A batch must create at least one todo")]
+    EmptyBatch,
+    #[msg("Each todo in the batch needs its account, in order")]
+    BatchAccountsMismatch,
+    #[msg("Permissions must combine status, description and delete flags")]
+    InvalidPermissions,
+    #[msg("Todos cannot be delegated to their owner")]
+    InvalidDelegate,
+    #[msg("The delegation does not permit this change")]
+    MissingPermission,
+    #[msg("Todos cannot be transferred to their owner")]
+    InvalidRecipient,
+    #[msg("Only the recipient can accept this transfer")]
+    NotTransferRecipient,
+    #[msg("The todo uses an older account layout and must be migrated first")]
+    AccountNeedsMigration,
+    #[msg("The transfer offer was made for an earlier todo at this address")]
+    StaleTransferOffer,
+}
+
+impl TodoError {
+    // Every variant, so off-chain clients can map custom error codes back to names
+    pub const ALL: [TodoError; 21] = [
+        TodoError::DescriptionTooLong,
+        TodoError::InvalidDueDate,
+        TodoError::UnauthorizedAccess,
+        TodoError::AlreadyMigrated,
+        TodoError::NothingToUpdate,
+        TodoError::InvalidStatusTransition,
+        TodoError::InvalidPriority,
+        TodoError::TooManyTags,
+        TodoError::InvalidTag,
+        TodoError::UnsupportedVersion,
+        TodoError::ProfileHasTodos,
+        TodoError::TodoNotCompleted,
+        TodoError::EmptyBatch,
+        TodoError::BatchAccountsMismatch,
+        TodoError::InvalidPermissions,
+        TodoError::InvalidDelegate,
+        TodoError::MissingPermission,
+        TodoError::InvalidRecipient,
+        TodoError::NotTransferRecipient,
+        TodoError::AccountNeedsMigration,
+        TodoError::StaleTransferOffer,
+    ];
 }
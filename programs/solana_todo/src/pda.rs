@@ -0,0 +1,34 @@
+use anchor_lang::prelude::Pubkey;
+
+// Seeds shared by the program's account constraints and off-chain clients.
+// Changing any of them moves every existing account, so treat them as fixed.
+pub const USER_PROFILE_SEED: &[u8] = b"user-profile";
+pub const TODO_SEED: &[u8] = b"todo";
+pub const DELEGATION_SEED: &[u8] = b"delegation";
+pub const TRANSFER_SEED: &[u8] = b"transfer";
+
+// Derive the UserProfile address for `authority`
+pub fn user_profile_address(authority: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USER_PROFILE_SEED, authority.as_ref()], program_id)
+}
+
+// Derive the TodoItem address for `authority`'s todo with the given id
+pub fn todo_address(authority: &Pubkey, todo_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[TODO_SEED, authority.as_ref(), &todo_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+// Derive the Delegation address for `owner`'s delegation to `delegate`
+pub fn delegation_address(owner: &Pubkey, delegate: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DELEGATION_SEED, owner.as_ref(), delegate.as_ref()],
+        program_id,
+    )
+}
+
+// Derive the TransferOffer address for the todo account `todo`
+pub fn transfer_offer_address(todo: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TRANSFER_SEED, todo.as_ref()], program_id)
+}
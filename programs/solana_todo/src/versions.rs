@@ -0,0 +1,263 @@
+// Account layouts written by earlier releases of the program, and decoders
+// that read an account in whichever layout it was stored.
+//
+// Accounts carry a version byte right after the discriminator. Accounts
+// written before that byte existed were allocated at a fixed size per
+// layout, so their size tells them apart; they are numbered here as the
+// implicit versions below the first versioned layout. Todos are now sized
+// to their description and can land on one of those sizes too, so a todo
+// only counts as unversioned when it does not read as a versioned todo that
+// fills its account exactly.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{TodoError, TodoItem, TodoStatus, UserProfile};
+
+// Todo layout from before timestamps were recorded (implicit version 0)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TodoItemV0 {
+    pub id: u64,
+    pub description: String,
+    pub completed: bool,
+    pub due_date: i64,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl TodoItemV0 {
+    // The size these accounts were created with, which left out the
+    // description's length prefix
+    pub const SPACE: usize = 8 + 8 + 280 + 1 + 8 + 32 + 32;
+}
+
+// Todo layout from before priorities and tags were stored (implicit version 1)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TodoItemV1 {
+    pub id: u64,
+    pub description: String,
+    pub status: TodoStatus,
+    pub due_date: i64,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+impl TodoItemV1 {
+    pub const SPACE: usize = 8 + 8 + 4 + 280 + 1 + 8 + 32 + 32 + 8 + 8 + 1 + 8;
+}
+
+impl From<TodoItemV0> for TodoItemV1 {
+    fn from(v0: TodoItemV0) -> Self {
+        Self {
+            id: v0.id,
+            description: v0.description,
+            status: if v0.completed { TodoStatus::Done } else { TodoStatus::Todo },
+            due_date: v0.due_date,
+            owner: v0.owner,
+            authority: v0.authority,
+            created_at: 0,
+            updated_at: 0,
+            completed_at: None,
+        }
+    }
+}
+
+// Todo layout from before the version byte (implicit version 2)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TodoItemV2 {
+    pub id: u64,
+    pub description: String,
+    pub status: TodoStatus,
+    pub due_date: i64,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+    pub priority: u8,
+    pub tags: Vec<String>,
+}
+
+impl TodoItemV2 {
+    pub const SPACE: usize = TodoItemV1::SPACE + 1 + 4 + TodoItem::MAX_TAGS * (4 + TodoItem::MAX_TAG_LEN);
+}
+
+impl From<TodoItemV1> for TodoItemV2 {
+    fn from(v1: TodoItemV1) -> Self {
+        Self {
+            id: v1.id,
+            description: v1.description,
+            status: v1.status,
+            due_date: v1.due_date,
+            owner: v1.owner,
+            authority: v1.authority,
+            created_at: v1.created_at,
+            updated_at: v1.updated_at,
+            completed_at: v1.completed_at,
+            priority: 0,
+            tags: Vec::new(),
+        }
+    }
+}
+
+// Todo layout with the owner after the description, where its offset
+// depends on the description's length
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TodoItemV3 {
+    pub version: u8,
+    pub id: u64,
+    pub description: String,
+    pub status: TodoStatus,
+    pub due_date: i64,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+    pub priority: u8,
+    pub tags: Vec<String>,
+}
+
+impl From<TodoItemV3> for TodoItem {
+    fn from(v3: TodoItemV3) -> Self {
+        Self {
+            version: TodoItem::VERSION,
+            owner: v3.owner,
+            id: v3.id,
+            description: v3.description,
+            status: v3.status,
+            due_date: v3.due_date,
+            authority: v3.authority,
+            created_at: v3.created_at,
+            updated_at: v3.updated_at,
+            completed_at: v3.completed_at,
+            priority: v3.priority,
+            tags: v3.tags,
+        }
+    }
+}
+
+impl From<TodoItemV2> for TodoItem {
+    fn from(v2: TodoItemV2) -> Self {
+        Self {
+            version: TodoItem::VERSION,
+            owner: v2.owner,
+            id: v2.id,
+            description: v2.description,
+            status: v2.status,
+            due_date: v2.due_date,
+            authority: v2.authority,
+            created_at: v2.created_at,
+            updated_at: v2.updated_at,
+            completed_at: v2.completed_at,
+            priority: v2.priority,
+            tags: v2.tags,
+        }
+    }
+}
+
+impl TodoItem {
+    // The version a todo account is stored in, or None if it is not a todo account
+    pub fn stored_version(data: &[u8]) -> Option<u8> {
+        if !data.starts_with(&Self::DISCRIMINATOR) {
+            return None;
+        }
+
+        let unversioned = match data.len() {
+            TodoItemV0::SPACE => Some(0),
+            TodoItemV1::SPACE => Some(1),
+            TodoItemV2::SPACE => Some(2),
+            _ => None,
+        };
+        match unversioned {
+            Some(version) if !Self::is_right_sized(data) => Some(version),
+            _ => data.get(8).copied(),
+        }
+    }
+
+    // Whether `data` holds a versioned todo in an account sized to its
+    // description. Version 3 holds the same fields as the current layout,
+    // so both take the same space.
+    fn is_right_sized(data: &[u8]) -> bool {
+        let mut payload = &data[8..];
+        let description = match data.get(8) {
+            Some(&Self::VERSION) => Self::deserialize(&mut payload).map(|todo| todo.description),
+            Some(3) => TodoItemV3::deserialize(&mut payload).map(|v3| v3.description),
+            _ => return false,
+        };
+        matches!(description, Ok(description) if Self::space(&description) == data.len())
+    }
+
+    // Decode a todo account stored in any version, upgraded to the current
+    // layout. Fields an old layout lacked take their defaults.
+    pub fn try_deserialize_any_version(data: &[u8]) -> Result<Self> {
+        let version = Self::stored_version(data)
+            .ok_or_else(|| error!(ErrorCode::AccountDiscriminatorMismatch))?;
+
+        let mut payload = &data[8..];
+        let todo = match version {
+            0 => TodoItemV0::deserialize(&mut payload)
+                .map(|v0| Self::from(TodoItemV2::from(TodoItemV1::from(v0)))),
+            1 => TodoItemV1::deserialize(&mut payload).map(|v1| Self::from(TodoItemV2::from(v1))),
+            2 => TodoItemV2::deserialize(&mut payload).map(Self::from),
+            3 => TodoItemV3::deserialize(&mut payload).map(Self::from),
+            Self::VERSION => Self::deserialize(&mut payload),
+            _ => return err!(TodoError::UnsupportedVersion),
+        };
+        todo.map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
+// Profile layout from before the version byte (implicit version 0)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UserProfileV0 {
+    pub authority: Pubkey,
+    pub todo_count: u64,
+    pub last_todo_id: u64,
+}
+
+impl UserProfileV0 {
+    pub const SPACE: usize = 8 + 32 + 8 + 8;
+}
+
+impl From<UserProfileV0> for UserProfile {
+    fn from(v0: UserProfileV0) -> Self {
+        Self {
+            version: UserProfile::VERSION,
+            authority: v0.authority,
+            todo_count: v0.todo_count,
+            last_todo_id: v0.last_todo_id,
+        }
+    }
+}
+
+impl UserProfile {
+    // The version a profile account is stored in, or None if it is not a profile account
+    pub fn stored_version(data: &[u8]) -> Option<u8> {
+        if !data.starts_with(&Self::DISCRIMINATOR) {
+            return None;
+        }
+
+        match data.len() {
+            UserProfileV0::SPACE => Some(0),
+            _ => data.get(8).copied(),
+        }
+    }
+
+    // Decode a profile account stored in any version, upgraded to the current layout
+    pub fn try_deserialize_any_version(data: &[u8]) -> Result<Self> {
+        let version = Self::stored_version(data)
+            .ok_or_else(|| error!(ErrorCode::AccountDiscriminatorMismatch))?;
+
+        let mut payload = &data[8..];
+        let profile = match version {
+            0 => UserProfileV0::deserialize(&mut payload).map(Self::from),
+            Self::VERSION => Self::deserialize(&mut payload),
+            _ => return err!(TodoError::UnsupportedVersion),
+        };
+        profile.map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
@@ -0,0 +1,2305 @@
+use anchor_lang::{
+    error::ErrorCode as AnchorErrorCode,
+    solana_program::{account_info::AccountInfo, entrypoint::ProgramResult},
+    AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, InstructionData,
+    ToAccountMetas,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+use solana_todo::{
+    accounts, instruction, pda,
+    versions::{TodoItemV0, TodoItemV1, TodoItemV2, TodoItemV3, UserProfileV0},
+    Delegation, NewTodo, TodoError, TodoItem, TodoStatus, TransferOffer, UserProfile,
+};
+
+// Anchor's entrypoint ties the account slice to its own lifetime, which the
+// processor! signature cannot express; leaking the slice is fine in tests
+fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    solana_todo::entry(program_id, accounts, data)
+}
+
+// The compiled program, as a validator runs it: `cargo build-sbf` writes
+// solana_todo.so to target/deploy, or SBF_OUT_DIR when set. Where the SBF
+// toolchain is missing, SOLANA_TODO_NATIVE=1 runs the native build instead.
+fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::default();
+    if std::env::var_os("SOLANA_TODO_NATIVE").is_some() {
+        program_test.prefer_bpf(false);
+        program_test.add_program("solana_todo", solana_todo::ID, processor!(process_instruction));
+        return program_test;
+    }
+
+    let out_dir = std::env::var("SBF_OUT_DIR")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy").to_string());
+    let program = std::path::Path::new(&out_dir).join("solana_todo.so");
+    assert!(
+        program.is_file(),
+        "{} not found; build the program with `cargo build-sbf` or set SOLANA_TODO_NATIVE=1",
+        program.display()
+    );
+    std::env::set_var("SBF_OUT_DIR", &out_dir);
+    program_test.prefer_bpf(true);
+    program_test.add_program("solana_todo", solana_todo::ID, None);
+    program_test
+}
+
+// Give `wallet` enough lamports to pay rent for its accounts
+fn fund(program_test: &mut ProgramTest, wallet: &Keypair) {
+    program_test.add_account(
+        wallet.pubkey(),
+        Account::new(10 * LAMPORTS_PER_SOL, 0, &system_program::id()),
+    );
+}
+
+// Start a bank with a funded authority; the context payer covers fees
+async fn start(authority: &Keypair) -> ProgramTestContext {
+    let mut program_test = program_test();
+    fund(&mut program_test, authority);
+    program_test.start_with_context().await
+}
+
+async fn process(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    ctx.banks_client.process_transaction(transaction).await
+}
+
+fn custom_error(result: Result<(), BanksClientError>) -> u32 {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => code,
+        other => panic!("expected a custom program error, got {:?}", other),
+    }
+}
+
+fn profile_address(authority: &Pubkey) -> Pubkey {
+    pda::user_profile_address(authority, &solana_todo::ID).0
+}
+
+fn todo_address(authority: &Pubkey, todo_id: u64) -> Pubkey {
+    pda::todo_address(authority, todo_id, &solana_todo::ID).0
+}
+
+fn initialize_user(authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::InitializeUser {
+            user_profile: profile_address(authority),
+            authority: *authority,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeUser {}.data(),
+    }
+}
+
+fn create_todo(authority: &Pubkey, todo_id: u64, description: &str, due_date: i64) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::CreateTodo {
+            user_profile: profile_address(authority),
+            todo_account: todo_address(authority, todo_id),
+            authority: *authority,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::CreateTodo {
+            description: description.to_string(),
+            due_date,
+        }
+        .data(),
+    }
+}
+
+fn create_todos_batch(authority: &Pubkey, todo_accounts: &[Pubkey], todos: &[(&str, i64)]) -> Instruction {
+    let mut accounts = accounts::CreateTodosBatch {
+        user_profile: profile_address(authority),
+        authority: *authority,
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+    accounts.extend(todo_accounts.iter().map(|todo_account| AccountMeta::new(*todo_account, false)));
+
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts,
+        data: instruction::CreateTodosBatch {
+            todos: todos
+                .iter()
+                .map(|(description, due_date)| NewTodo {
+                    description: description.to_string(),
+                    due_date: *due_date,
+                })
+                .collect(),
+        }
+        .data(),
+    }
+}
+
+fn update_todo_status(todo_account: Pubkey, authority: &Pubkey, completed: bool) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateTodoStatus { completed }.data(),
+    }
+}
+
+fn set_status(todo_account: Pubkey, authority: &Pubkey, status: TodoStatus) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::SetStatus { status }.data(),
+    }
+}
+
+fn update_description(todo_account: Pubkey, authority: &Pubkey, description: &str) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateDescription {
+            description: description.to_string(),
+        }
+        .data(),
+    }
+}
+
+fn update_due_date(todo_account: Pubkey, authority: &Pubkey, due_date: i64) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateDueDate { due_date }.data(),
+    }
+}
+
+fn set_priority(todo_account: Pubkey, authority: &Pubkey, priority: u8) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::SetPriority { priority }.data(),
+    }
+}
+
+fn set_tags(todo_account: Pubkey, authority: &Pubkey, tags: &[&str]) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::SetTags {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+        .data(),
+    }
+}
+
+fn update_todo(
+    todo_account: Pubkey,
+    authority: &Pubkey,
+    description: Option<&str>,
+    status: Option<TodoStatus>,
+    due_date: Option<i64>,
+    priority: Option<u8>,
+    tags: Option<&[&str]>,
+) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateTodo {
+            todo_account,
+            authority: *authority,
+            owner: *authority,
+            system_program: system_program::id(),
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateTodo {
+            description: description.map(str::to_string),
+            status,
+            due_date,
+            priority,
+            tags: tags.map(|tags| tags.iter().map(|tag| tag.to_string()).collect()),
+        }
+        .data(),
+    }
+}
+
+fn delete_todo(todo_account: Pubkey, authority: &Pubkey) -> Instruction {
+    delete_owned_todo(authority, todo_account, authority)
+}
+
+// Delete one of `owner`'s todos, signed by `authority`
+fn delete_owned_todo(owner: &Pubkey, todo_account: Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::DeleteTodo {
+            user_profile: profile_address(owner),
+            todo_account,
+            authority: *authority,
+            owner: *owner,
+            delegation: None,
+        }
+        .to_account_metas(None),
+        data: instruction::DeleteTodo {}.data(),
+    }
+}
+
+fn delegation_address(owner: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    pda::delegation_address(owner, delegate, &solana_todo::ID).0
+}
+
+// The update helpers name their signer as the todo's owner; point an
+// update signed by `signer` at `owner`'s todo instead
+fn for_owner(mut instruction: Instruction, owner: &Pubkey, signer: &Pubkey) -> Instruction {
+    for account in &mut instruction.accounts {
+        if account.pubkey == *signer && !account.is_signer {
+            account.pubkey = *owner;
+        }
+    }
+    instruction
+}
+
+// Pass `delegate`'s delegation from `owner` in an update or delete
+// instruction built with no delegation, where it is the last account
+fn with_delegation(instruction: Instruction, owner: &Pubkey, delegate: &Pubkey) -> Instruction {
+    let mut instruction = for_owner(instruction, owner, delegate);
+    *instruction.accounts.last_mut().unwrap() = AccountMeta::new_readonly(delegation_address(owner, delegate), false);
+    instruction
+}
+
+fn grant_delegation(owner: &Pubkey, delegate: &Pubkey, permissions: u8) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::GrantDelegation {
+            delegation: delegation_address(owner, delegate),
+            delegate: *delegate,
+            owner: *owner,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::GrantDelegation { permissions }.data(),
+    }
+}
+
+fn update_delegation(owner: &Pubkey, delegate: &Pubkey, permissions: u8) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::UpdateDelegation {
+            delegation: delegation_address(owner, delegate),
+            owner: *owner,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateDelegation { permissions }.data(),
+    }
+}
+
+fn revoke_delegation(owner: &Pubkey, delegate: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::RevokeDelegation {
+            delegation: delegation_address(owner, delegate),
+            owner: *owner,
+        }
+        .to_account_metas(None),
+        data: instruction::RevokeDelegation {}.data(),
+    }
+}
+
+fn transfer_offer_address(todo_account: &Pubkey) -> Pubkey {
+    pda::transfer_offer_address(todo_account, &solana_todo::ID).0
+}
+
+fn offer_transfer(todo_account: Pubkey, authority: &Pubkey, recipient: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::OfferTransfer {
+            transfer_offer: transfer_offer_address(&todo_account),
+            todo_account,
+            recipient: *recipient,
+            authority: *authority,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::OfferTransfer {}.data(),
+    }
+}
+
+// Accept the offer of `owner`'s todo, moving it to `recipient`'s todo `new_todo_id`
+fn transfer_todo(todo_account: Pubkey, owner: &Pubkey, recipient: &Pubkey, new_todo_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::TransferTodo {
+            transfer_offer: transfer_offer_address(&todo_account),
+            todo_account,
+            owner_profile: profile_address(owner),
+            owner: *owner,
+            recipient_profile: profile_address(recipient),
+            new_todo_account: todo_address(recipient, new_todo_id),
+            recipient: *recipient,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::TransferTodo {}.data(),
+    }
+}
+
+// Pass the pending offer of `todo_account` as a remaining account, for the
+// instructions that close it along with the todo
+fn with_offer(mut instruction: Instruction, todo_account: &Pubkey) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new(transfer_offer_address(todo_account), false));
+    instruction
+}
+
+fn cancel_transfer(todo_account: Pubkey, owner: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::CancelTransfer {
+            transfer_offer: transfer_offer_address(&todo_account),
+            owner: *owner,
+            authority: *authority,
+        }
+        .to_account_metas(None),
+        data: instruction::CancelTransfer {}.data(),
+    }
+}
+
+fn delete_completed_todos(authority: &Pubkey, todo_accounts: &[Pubkey]) -> Instruction {
+    let mut accounts = accounts::DeleteCompletedTodos {
+        user_profile: profile_address(authority),
+        authority: *authority,
+    }
+    .to_account_metas(None);
+    accounts.extend(todo_accounts.iter().map(|todo_account| AccountMeta::new(*todo_account, false)));
+
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts,
+        data: instruction::DeleteCompletedTodos {}.data(),
+    }
+}
+
+fn delete_todos(authority: &Pubkey, todo_accounts: &[Pubkey]) -> Instruction {
+    let mut accounts = accounts::DeleteTodos {
+        user_profile: profile_address(authority),
+        authority: *authority,
+    }
+    .to_account_metas(None);
+    accounts.extend(todo_accounts.iter().map(|todo_account| AccountMeta::new(*todo_account, false)));
+
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts,
+        data: instruction::DeleteTodos {}.data(),
+    }
+}
+
+fn close_user(authority: &Pubkey, todo_accounts: &[Pubkey]) -> Instruction {
+    let mut accounts = accounts::CloseUser {
+        user_profile: profile_address(authority),
+        authority: *authority,
+    }
+    .to_account_metas(None);
+    accounts.extend(todo_accounts.iter().map(|todo_account| AccountMeta::new(*todo_account, false)));
+
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts,
+        data: instruction::CloseUser {}.data(),
+    }
+}
+
+fn migrate_profile(authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::MigrateProfile {
+            user_profile: profile_address(authority),
+            authority: *authority,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateProfile {}.data(),
+    }
+}
+
+fn migrate_todo(todo_account: Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_todo::ID,
+        accounts: accounts::MigrateTodo {
+            todo_account,
+            authority: *authority,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateTodo {}.data(),
+    }
+}
+
+// Move to a later slot, so repeated transactions get a fresh blockhash, and
+// pin the cluster time
+async fn set_time(ctx: &mut ProgramTestContext, slot: u64, unix_timestamp: i64) {
+    ctx.warp_to_slot(slot).unwrap();
+    let mut clock = ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    ctx.set_sysvar(&clock);
+}
+
+async fn fetch<T: AccountDeserialize>(ctx: &mut ProgramTestContext, address: Pubkey) -> Option<T> {
+    ctx.banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .map(|account| T::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+// Initialize the authority's profile and create todo #1
+async fn setup_with_todo(authority: &Keypair) -> ProgramTestContext {
+    let mut ctx = start(authority).await;
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&authority.pubkey()),
+            create_todo(&authority.pubkey(), 1, "First", 1625097600),
+        ],
+        &[authority],
+    )
+    .await
+    .unwrap();
+    ctx
+}
+
+#[tokio::test]
+async fn test_initialize_user() {
+    let authority = Keypair::new();
+    let mut ctx = start(&authority).await;
+
+    process(&mut ctx, &[initialize_user(&authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&authority.pubkey())).await.unwrap();
+    assert_eq!(profile.version, UserProfile::VERSION);
+    assert_eq!(profile.authority, authority.pubkey());
+    assert_eq!(profile.todo_count, 0);
+    assert_eq!(profile.last_todo_id, 0);
+
+    // A second initialization fails because the profile account already exists
+    ctx.warp_to_slot(2).unwrap();
+    assert!(process(&mut ctx, &[initialize_user(&authority.pubkey())], &[&authority])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_create_todo() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+
+    // The account is sized to the description; the limit counts characters,
+    // so the longest description takes two bytes per character here
+    let longest = "é".repeat(TodoItem::MAX_DESCRIPTION_CHARS);
+    process(
+        &mut ctx,
+        &[create_todo(&authority.pubkey(), 2, &longest, 0)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    for (id, description) in [(1, "First"), (2, longest.as_str())] {
+        let account = ctx.banks_client.get_account(todo_address(&authority.pubkey(), id)).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), TodoItem::space(description));
+        assert_eq!(account.lamports, Rent::default().minimum_balance(account.data.len()));
+    }
+
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&authority.pubkey())).await.unwrap();
+    assert_eq!(profile.todo_count, 2);
+    assert_eq!(profile.last_todo_id, 2);
+
+    let first: TodoItem = fetch(&mut ctx, todo_address(&authority.pubkey(), 1)).await.unwrap();
+    assert_eq!(first.version, TodoItem::VERSION);
+    assert_eq!(first.id, 1);
+    assert_eq!(first.description, "First");
+    assert!(!first.completed());
+    assert_eq!(first.due_date, 1625097600);
+    assert_eq!(first.owner, authority.pubkey());
+    assert_eq!(first.authority, authority.pubkey());
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    assert_eq!(first.created_at, now);
+    assert_eq!(first.updated_at, now);
+    assert_eq!(first.completed_at, None);
+
+    let second: TodoItem = fetch(&mut ctx, todo_address(&authority.pubkey(), 2)).await.unwrap();
+    assert_eq!(second.id, 2);
+    assert_eq!(second.description, longest);
+}
+
+#[tokio::test]
+async fn test_create_todo_requires_next_todo_id() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+
+    // Todo #1 exists; the next create must target #2
+    let result = process(
+        &mut ctx,
+        &[create_todo(&authority.pubkey(), 3, "Skipped ahead", 0)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn test_create_todo_rejects_long_description() {
+    let authority = Keypair::new();
+    let mut ctx = start(&authority).await;
+
+    let result = process(
+        &mut ctx,
+        &[
+            initialize_user(&authority.pubkey()),
+            create_todo(&authority.pubkey(), 1, &"é".repeat(TodoItem::MAX_DESCRIPTION_CHARS + 1), 0),
+        ],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::DescriptionTooLong));
+}
+
+#[tokio::test]
+async fn test_create_todo_rejects_negative_due_date() {
+    let authority = Keypair::new();
+    let mut ctx = start(&authority).await;
+
+    let result = process(
+        &mut ctx,
+        &[
+            initialize_user(&authority.pubkey()),
+            create_todo(&authority.pubkey(), 1, "Yesterday", -1),
+        ],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidDueDate));
+}
+
+#[tokio::test]
+async fn test_create_todos_batch_assigns_sequential_ids() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+
+    // Lamports sent to an address ahead of time don't block its creation
+    let prefunded = todo_address(&owner, 3);
+    let payer = ctx.payer.pubkey();
+    let lamports = Rent::default().minimum_balance(0);
+    process(&mut ctx, &[system_instruction::transfer(&payer, &prefunded, lamports)], &[])
+        .await
+        .unwrap();
+
+    let todos = [("Plan the sprint", 1625097600), ("Réviser", 0), ("Demo", 1625184000)];
+    let addresses: Vec<Pubkey> = (2..=4).map(|id| todo_address(&owner, id)).collect();
+    process(&mut ctx, &[create_todos_batch(&owner, &addresses, &todos)], &[&authority])
+        .await
+        .unwrap();
+
+    for ((id, (description, due_date)), address) in (2..).zip(todos).zip(&addresses) {
+        let account = ctx.banks_client.get_account(*address).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), TodoItem::space(description));
+        assert_eq!(account.lamports, Rent::default().minimum_balance(account.data.len()));
+
+        let todo = TodoItem::try_deserialize(&mut account.data.as_slice()).unwrap();
+        assert_eq!(todo.id, id);
+        assert_eq!(todo.description, description);
+        assert_eq!(todo.due_date, due_date);
+        assert_eq!(todo.status, TodoStatus::Todo);
+        assert_eq!((todo.owner, todo.authority), (owner, owner));
+    }
+
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!((profile.last_todo_id, profile.todo_count), (4, 4));
+
+    // Single creates carry on from the batch
+    process(&mut ctx, &[create_todo(&owner, 5, "After the batch", 0)], &[&authority])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_create_todos_batch_validates_items_and_accounts() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+    let addresses: Vec<Pubkey> = (2..=3).map(|id| todo_address(&owner, id)).collect();
+
+    let result = process(&mut ctx, &[create_todos_batch(&owner, &[], &[])], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::EmptyBatch));
+
+    let result = process(
+        &mut ctx,
+        &[create_todos_batch(&owner, &addresses[..1], &[("One", 0), ("Two", 0)])],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::BatchAccountsMismatch));
+
+    // Accounts must be the PDAs of the ids being assigned, in order
+    let swapped = [addresses[1], addresses[0]];
+    let result = process(
+        &mut ctx,
+        &[create_todos_batch(&owner, &swapped, &[("One", 0), ("Two", 0)])],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+
+    // One bad item fails the whole batch
+    let too_long = "a".repeat(TodoItem::MAX_DESCRIPTION_CHARS + 1);
+    let result = process(
+        &mut ctx,
+        &[create_todos_batch(&owner, &addresses, &[("Fine", 0), (&too_long, 0)])],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::DescriptionTooLong));
+    let result = process(
+        &mut ctx,
+        &[create_todos_batch(&owner, &addresses, &[("Fine", 0), ("Past", -1)])],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidDueDate));
+
+    assert!(ctx.banks_client.get_account(addresses[0]).await.unwrap().is_none());
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!((profile.last_todo_id, profile.todo_count), (1, 1));
+}
+
+#[tokio::test]
+async fn test_update_todo_status() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+    let created: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    process(
+        &mut ctx,
+        &[update_todo_status(todo_account, &authority.pubkey(), true)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert!(todo.completed());
+    assert_eq!(todo.created_at, created.created_at);
+    assert_eq!(todo.updated_at, 1_700_000_000);
+    assert_eq!(todo.completed_at, Some(1_700_000_000));
+
+    // Completing an already completed todo keeps the original completion time
+    set_time(&mut ctx, 3, 1_700_000_100).await;
+    process(
+        &mut ctx,
+        &[update_todo_status(todo_account, &authority.pubkey(), true)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.updated_at, 1_700_000_100);
+    assert_eq!(todo.completed_at, Some(1_700_000_000));
+
+    process(
+        &mut ctx,
+        &[update_todo_status(todo_account, &authority.pubkey(), false)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert!(!todo.completed());
+    assert_eq!(todo.completed_at, None);
+}
+
+#[tokio::test]
+async fn test_set_status_follows_workflow() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    let mut slot = 1;
+    let mut move_to = |status: TodoStatus| {
+        slot += 1;
+        (slot, set_status(todo_account, &authority.pubkey(), status))
+    };
+
+    for (status, allowed) in [
+        (TodoStatus::InProgress, true),
+        (TodoStatus::Blocked, true),
+        // Blocked work has to be unblocked before it is finished
+        (TodoStatus::Done, false),
+        (TodoStatus::InProgress, true),
+        (TodoStatus::Done, true),
+        // Finished work can be reopened but not cancelled
+        (TodoStatus::Cancelled, false),
+        (TodoStatus::Todo, true),
+        (TodoStatus::Cancelled, true),
+        (TodoStatus::InProgress, false),
+        (TodoStatus::Todo, true),
+    ] {
+        let (slot, instruction) = move_to(status);
+        set_time(&mut ctx, slot, 1_700_000_000 + slot as i64).await;
+        let result = process(&mut ctx, &[instruction], &[&authority]).await;
+
+        let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+        if allowed {
+            result.unwrap();
+            assert_eq!(todo.status, status);
+            assert_eq!(todo.completed(), status == TodoStatus::Done);
+            assert_eq!(todo.completed_at.is_some(), status == TodoStatus::Done);
+        } else {
+            assert_eq!(custom_error(result), u32::from(TodoError::InvalidStatusTransition));
+            assert_ne!(todo.status, status);
+        }
+    }
+
+    // The boolean instruction respects the workflow too
+    process(
+        &mut ctx,
+        &[set_status(todo_account, &authority.pubkey(), TodoStatus::Blocked)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut ctx,
+        &[update_todo_status(todo_account, &authority.pubkey(), true)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidStatusTransition));
+}
+
+// Todos stored `completed: bool` where the status byte now sits
+#[test]
+fn test_status_shares_the_boolean_encoding() {
+    assert_eq!(false.try_to_vec().unwrap(), TodoStatus::Todo.try_to_vec().unwrap());
+    assert_eq!(true.try_to_vec().unwrap(), TodoStatus::Done.try_to_vec().unwrap());
+    assert_eq!(
+        Some(true).try_to_vec().unwrap(),
+        Some(TodoStatus::Done).try_to_vec().unwrap()
+    );
+}
+
+// TodoItem implements the account traits by hand, so check it keeps the
+// discriminator #[account] would give it
+#[test]
+fn test_todo_discriminator_matches_anchor() {
+    let hash = anchor_lang::solana_program::hash::hash(b"account:TodoItem");
+    assert_eq!(TodoItem::DISCRIMINATOR, hash.to_bytes()[..8]);
+}
+
+#[tokio::test]
+async fn test_update_description() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    process(
+        &mut ctx,
+        &[update_description(todo_account, &authority.pubkey(), "Renamed")],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "Renamed");
+    assert_eq!(todo.updated_at, 1_700_000_000);
+
+    let result = process(
+        &mut ctx,
+        &[update_description(todo_account, &authority.pubkey(), &"x".repeat(281))],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::DescriptionTooLong));
+}
+
+#[tokio::test]
+async fn test_description_edits_resize_account() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    // Growing charges the authority the extra rent; the context payer covers fees
+    let balance_before = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+    let rent_before = ctx.banks_client.get_balance(todo_account).await.unwrap();
+    let longer = "A much longer description than the first one";
+    process(
+        &mut ctx,
+        &[update_description(todo_account, &authority.pubkey(), longer)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx.banks_client.get_account(todo_account).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), TodoItem::space(longer));
+    assert_eq!(account.lamports, Rent::default().minimum_balance(TodoItem::space(longer)));
+    let balance_after = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+    assert_eq!(balance_before - balance_after, account.lamports - rent_before);
+
+    // Shrinking through update_todo refunds the difference
+    ctx.warp_to_slot(2).unwrap();
+    let balance_before = balance_after;
+    let rent_before = account.lamports;
+    process(
+        &mut ctx,
+        &[update_todo(todo_account, &authority.pubkey(), Some("Short"), None, None, Some(2), None)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx.banks_client.get_account(todo_account).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), TodoItem::space("Short"));
+    assert_eq!(account.lamports, Rent::default().minimum_balance(TodoItem::space("Short")));
+    let balance_after = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+    assert_eq!(balance_after - balance_before, rent_before - account.lamports);
+
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "Short");
+    assert_eq!(todo.priority, 2);
+
+    // Updates that leave the description alone keep the size
+    process(&mut ctx, &[set_tags(todo_account, &authority.pubkey(), &["a", "b"])], &[&authority])
+        .await
+        .unwrap();
+    let account = ctx.banks_client.get_account(todo_account).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), TodoItem::space("Short"));
+}
+
+#[tokio::test]
+async fn test_right_sized_todos_are_not_mistaken_for_unversioned_layouts() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+
+    // Descriptions that size current todos exactly like each unversioned layout
+    let sizes = [TodoItemV0::SPACE, TodoItemV1::SPACE, TodoItemV2::SPACE];
+    let instructions: Vec<Instruction> = sizes
+        .iter()
+        .enumerate()
+        .map(|(index, size)| {
+            create_todo(&owner, index as u64 + 2, &"x".repeat(size - TodoItem::BASE_SPACE), 0)
+        })
+        .collect();
+    process(&mut ctx, &instructions, &[&authority]).await.unwrap();
+
+    for (index, size) in sizes.into_iter().enumerate() {
+        let todo_account = todo_address(&owner, index as u64 + 2);
+        let account = ctx.banks_client.get_account(todo_account).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), size);
+        assert_eq!(TodoItem::stored_version(&account.data), Some(TodoItem::VERSION));
+        let todo = TodoItem::try_deserialize_any_version(&account.data).unwrap();
+        assert_eq!(todo.description.len(), size - TodoItem::BASE_SPACE);
+
+        let result = process(&mut ctx, &[migrate_todo(todo_account, &owner)], &[&authority]).await;
+        assert_eq!(custom_error(result), u32::from(TodoError::AlreadyMigrated));
+    }
+}
+
+#[tokio::test]
+async fn test_update_due_date() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    process(
+        &mut ctx,
+        &[update_due_date(todo_account, &authority.pubkey(), 1625184000)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.due_date, 1625184000);
+    assert_eq!(todo.updated_at, 1_700_000_000);
+
+    let result = process(
+        &mut ctx,
+        &[update_due_date(todo_account, &authority.pubkey(), -1)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidDueDate));
+}
+
+#[tokio::test]
+async fn test_update_todo_changes_fields_together() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    process(
+        &mut ctx,
+        &[update_todo(
+            todo_account,
+            &authority.pubkey(),
+            Some("Rescheduled"),
+            Some(TodoStatus::Done),
+            Some(1625184000),
+            None,
+            None,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "Rescheduled");
+    assert!(todo.completed());
+    assert_eq!(todo.due_date, 1625184000);
+    assert_eq!(todo.updated_at, 1_700_000_000);
+    assert_eq!(todo.completed_at, Some(1_700_000_000));
+
+    // Fields left out are untouched
+    process(
+        &mut ctx,
+        &[update_todo(todo_account, &authority.pubkey(), None, None, Some(0), None, None)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "Rescheduled");
+    assert!(todo.completed());
+    assert_eq!(todo.due_date, 0);
+
+    // One invalid field rejects the whole update
+    let result = process(
+        &mut ctx,
+        &[update_todo(todo_account, &authority.pubkey(), Some("Valid"), None, Some(-1), None, None)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidDueDate));
+    let result = process(
+        &mut ctx,
+        &[update_todo(todo_account, &authority.pubkey(), Some(&"x".repeat(281)), Some(TodoStatus::Todo), None, None, None)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::DescriptionTooLong));
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "Rescheduled");
+    assert!(todo.completed());
+
+    let result = process(
+        &mut ctx,
+        &[update_todo(todo_account, &authority.pubkey(), None, None, None, None, None)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::NothingToUpdate));
+}
+
+#[tokio::test]
+async fn test_set_priority_and_tags() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.priority, 0);
+    assert!(todo.tags.is_empty());
+
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    process(
+        &mut ctx,
+        &[
+            set_priority(todo_account, &authority.pubkey(), TodoItem::MAX_PRIORITY),
+            set_tags(todo_account, &authority.pubkey(), &["work", "urgent"]),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.priority, TodoItem::MAX_PRIORITY);
+    assert_eq!(todo.tags, vec!["work", "urgent"]);
+    assert_eq!(todo.updated_at, 1_700_000_000);
+
+    let result = process(
+        &mut ctx,
+        &[set_priority(todo_account, &authority.pubkey(), TodoItem::MAX_PRIORITY + 1)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidPriority));
+
+    let too_many = ["a", "b", "c", "d", "e", "f"];
+    let result = process(&mut ctx, &[set_tags(todo_account, &authority.pubkey(), &too_many)], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::TooManyTags));
+
+    // Tags are limited in bytes, so eleven two-byte characters are too long
+    let too_long = "x".repeat(TodoItem::MAX_TAG_LEN + 1);
+    let too_long_accented = "é".repeat(TodoItem::MAX_TAG_LEN / 2 + 1);
+    for tags in [vec![too_long.as_str()], vec![too_long_accented.as_str()], vec![""], vec!["home", "home"]] {
+        let result = process(&mut ctx, &[set_tags(todo_account, &authority.pubkey(), &tags)], &[&authority]).await;
+        assert_eq!(custom_error(result), u32::from(TodoError::InvalidTag));
+    }
+
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.tags, vec!["work", "urgent"]);
+
+    // The largest todo the limits allow fits in the account
+    let longest_tag = "t".repeat(TodoItem::MAX_TAG_LEN);
+    let tags: Vec<String> = (0..TodoItem::MAX_TAGS)
+        .map(|index| format!("{}{}", index, &longest_tag[1..]))
+        .collect();
+    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+    process(
+        &mut ctx,
+        &[update_todo(
+            todo_account,
+            &authority.pubkey(),
+            Some(&"x".repeat(280)),
+            Some(TodoStatus::Done),
+            None,
+            Some(1),
+            Some(&tags),
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.priority, 1);
+    assert_eq!(todo.tags, tags);
+
+    // Ten two-byte characters fill a tag exactly
+    let accented = "é".repeat(TodoItem::MAX_TAG_LEN / 2);
+    process(&mut ctx, &[set_tags(todo_account, &authority.pubkey(), &[accented.as_str()])], &[&authority])
+        .await
+        .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.tags, vec![accented]);
+
+    // An empty list clears the tags
+    process(&mut ctx, &[set_tags(todo_account, &authority.pubkey(), &[])], &[&authority])
+        .await
+        .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert!(todo.tags.is_empty());
+}
+
+#[tokio::test]
+async fn test_updates_reject_other_signers() {
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    let result = process(
+        &mut ctx,
+        &[update_todo_status(todo_account, &intruder.pubkey(), true)],
+        &[&intruder],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::UnauthorizedAccess));
+
+    // Naming the real owner doesn't make the intruder the todo's authority
+    let update = for_owner(
+        update_todo_status(todo_account, &intruder.pubkey(), true),
+        &authority.pubkey(),
+        &intruder.pubkey(),
+    );
+    let result = process(&mut ctx, &[update], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert!(!todo.completed());
+}
+
+// The program always sets owner and authority together, so a todo whose
+// owner differs from its authority has to be planted directly
+#[tokio::test]
+async fn test_unauthorized_access_when_owner_differs() {
+    let authority = Keypair::new();
+    let todo_account = Pubkey::new_unique();
+    let todo = TodoItem {
+        version: TodoItem::VERSION,
+        id: 1,
+        description: "Planted".to_string(),
+        status: TodoStatus::Todo,
+        due_date: 0,
+        owner: Pubkey::new_unique(),
+        authority: authority.pubkey(),
+        created_at: 0,
+        updated_at: 0,
+        completed_at: None,
+        priority: 0,
+        tags: Vec::new(),
+    };
+    let mut data = Vec::new();
+    todo.try_serialize(&mut data).unwrap();
+
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    program_test.add_account(
+        todo_account,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let result = process(
+        &mut ctx,
+        &[update_todo_status(todo_account, &authority.pubkey(), true)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::UnauthorizedAccess));
+
+    // Deletes settle against the owner's profile, which the signer's isn't
+    let result = process(
+        &mut ctx,
+        &[
+            initialize_user(&authority.pubkey()),
+            delete_todo(todo_account, &authority.pubkey()),
+        ],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn test_delete_todo_refunds_rent() {
+    let authority = Keypair::new();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    let rent = ctx.banks_client.get_balance(todo_account).await.unwrap();
+    assert!(rent > 0);
+    let balance_before = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+
+    process(&mut ctx, &[delete_todo(todo_account, &authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+
+    // The context payer covers fees, so the authority gains exactly the rent
+    let balance_after = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+    assert_eq!(balance_after, balance_before + rent);
+    assert!(ctx.banks_client.get_account(todo_account).await.unwrap().is_none());
+
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&authority.pubkey())).await.unwrap();
+    assert_eq!(profile.todo_count, 0);
+    assert_eq!(profile.last_todo_id, 1);
+}
+
+#[tokio::test]
+async fn test_delete_todo_rejects_other_signers() {
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    fund(&mut program_test, &intruder);
+    let mut ctx = program_test.start_with_context().await;
+
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&authority.pubkey()),
+            create_todo(&authority.pubkey(), 1, "Mine", 0),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    process(&mut ctx, &[initialize_user(&intruder.pubkey())], &[&intruder])
+        .await
+        .unwrap();
+
+    // The intruder's own profile is not the todo owner's
+    let todo_account = todo_address(&authority.pubkey(), 1);
+    let result = process(&mut ctx, &[delete_todo(todo_account, &intruder.pubkey())], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+
+    // With the owner's accounts, the intruder still isn't the todo's authority
+    let delete = delete_owned_todo(&authority.pubkey(), todo_account, &intruder.pubkey());
+    let result = process(&mut ctx, &[delete], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+    assert!(ctx.banks_client.get_account(todo_account).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_delegates_act_within_their_permissions() {
+    let authority = Keypair::new();
+    let delegate = Keypair::new();
+    let (owner, collaborator) = (authority.pubkey(), delegate.pubkey());
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    fund(&mut program_test, &delegate);
+    let mut ctx = program_test.start_with_context().await;
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&owner),
+            create_todo(&owner, 1, "Shared", 0),
+            create_todo(&owner, 2, "Also shared", 0),
+            grant_delegation(&owner, &collaborator, Delegation::SET_STATUS),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo_account = todo_address(&owner, 1);
+
+    let delegation: Delegation = fetch(&mut ctx, delegation_address(&owner, &collaborator)).await.unwrap();
+    assert_eq!((delegation.owner, delegation.delegate), (owner, collaborator));
+    assert_eq!(delegation.permissions, Delegation::SET_STATUS);
+
+    // Without the delegation the collaborator is a stranger
+    let result = process(
+        &mut ctx,
+        &[for_owner(set_status(todo_account, &collaborator, TodoStatus::InProgress), &owner, &collaborator)],
+        &[&delegate],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+
+    let as_delegate = |instruction| with_delegation(instruction, &owner, &collaborator);
+    process(
+        &mut ctx,
+        &[as_delegate(set_status(todo_account, &collaborator, TodoStatus::InProgress))],
+        &[&delegate],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.status, TodoStatus::InProgress);
+    assert_eq!((todo.owner, todo.authority), (owner, owner));
+
+    // Permissions not granted, and changes reserved for the owner, are refused
+    for instruction in [
+        update_description(todo_account, &collaborator, "Renamed"),
+        delete_owned_todo(&owner, todo_account, &collaborator),
+        set_priority(todo_account, &collaborator, 2),
+        update_todo(todo_account, &collaborator, None, Some(TodoStatus::Done), Some(1), None, None),
+    ] {
+        let result = process(&mut ctx, &[as_delegate(instruction)], &[&delegate]).await;
+        assert_eq!(custom_error(result), u32::from(TodoError::MissingPermission));
+    }
+
+    process(
+        &mut ctx,
+        &[update_delegation(&owner, &collaborator, Delegation::ALL_PERMISSIONS)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut ctx,
+        &[as_delegate(update_todo(
+            todo_account,
+            &collaborator,
+            Some("Renamed by a collaborator"),
+            Some(TodoStatus::Done),
+            None,
+            None,
+            None,
+        ))],
+        &[&delegate],
+    )
+    .await
+    .unwrap();
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "Renamed by a collaborator");
+    assert_eq!(todo.status, TodoStatus::Done);
+
+    // A collaborator's delete settles with the owner: their count and their rent
+    let rent = ctx.banks_client.get_balance(todo_account).await.unwrap();
+    let owner_balance = ctx.banks_client.get_balance(owner).await.unwrap();
+    process(
+        &mut ctx,
+        &[as_delegate(delete_owned_todo(&owner, todo_account, &collaborator))],
+        &[&delegate],
+    )
+    .await
+    .unwrap();
+    assert!(ctx.banks_client.get_account(todo_account).await.unwrap().is_none());
+    assert_eq!(ctx.banks_client.get_balance(owner).await.unwrap(), owner_balance + rent);
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!(profile.todo_count, 1);
+
+    // Once revoked the delegation no longer exists to be passed
+    process(&mut ctx, &[revoke_delegation(&owner, &collaborator)], &[&authority])
+        .await
+        .unwrap();
+    assert!(ctx.banks_client.get_account(delegation_address(&owner, &collaborator)).await.unwrap().is_none());
+    let result = process(
+        &mut ctx,
+        &[as_delegate(set_status(todo_address(&owner, 2), &collaborator, TodoStatus::Done))],
+        &[&delegate],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::AccountNotInitialized));
+}
+
+#[tokio::test]
+async fn test_delegated_resizes_never_refund_the_collaborator() {
+    let authority = Keypair::new();
+    let delegate = Keypair::new();
+    let (owner, collaborator) = (authority.pubkey(), delegate.pubkey());
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    fund(&mut program_test, &delegate);
+    let mut ctx = program_test.start_with_context().await;
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&owner),
+            create_todo(&owner, 1, "Shared", 0),
+            grant_delegation(&owner, &collaborator, Delegation::EDIT_DESCRIPTION),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let todo_account = todo_address(&owner, 1);
+
+    async fn balances(ctx: &mut ProgramTestContext, accounts: [Pubkey; 3]) -> [u64; 3] {
+        let mut balances = [0; 3];
+        for (balance, account) in balances.iter_mut().zip(accounts) {
+            *balance = ctx.banks_client.get_balance(account).await.unwrap();
+        }
+        balances
+    }
+    let accounts = [todo_account, owner, collaborator];
+
+    // The collaborator pays for the room a longer description needs
+    let [rent, owner_balance, collaborator_balance] = balances(&mut ctx, accounts).await;
+    let edit = update_description(todo_account, &collaborator, &"x".repeat(200));
+    process(&mut ctx, &[with_delegation(edit, &owner, &collaborator)], &[&delegate])
+        .await
+        .unwrap();
+    let [grown_rent, owner_after, collaborator_after] = balances(&mut ctx, accounts).await;
+    assert_eq!(grown_rent, Rent::default().minimum_balance(TodoItem::space(&"x".repeat(200))));
+    assert_eq!(collaborator_balance - collaborator_after, grown_rent - rent);
+    assert_eq!(owner_after, owner_balance);
+
+    // ...but the rent a shorter one frees goes back to the owner
+    let edit = update_description(todo_account, &collaborator, "Short");
+    process(&mut ctx, &[with_delegation(edit, &owner, &collaborator)], &[&delegate])
+        .await
+        .unwrap();
+    let [shrunk_rent, owner_final, collaborator_final] = balances(&mut ctx, accounts).await;
+    assert_eq!(shrunk_rent, Rent::default().minimum_balance(TodoItem::space("Short")));
+    assert_eq!(owner_final - owner_after, grown_rent - shrunk_rent);
+    assert_eq!(collaborator_final, collaborator_after);
+
+    // Naming themselves as the owner doesn't redirect the refund
+    let edit = update_description(todo_account, &collaborator, "Shrunk again");
+    let mut edit = with_delegation(edit, &owner, &collaborator);
+    edit.accounts[2].pubkey = collaborator;
+    let result = process(&mut ctx, &[edit], &[&delegate]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::UnauthorizedAccess));
+}
+
+#[tokio::test]
+async fn test_grant_delegation_validates_permissions() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let collaborator = Pubkey::new_unique();
+    let mut ctx = setup_with_todo(&authority).await;
+
+    for permissions in [0, Delegation::ALL_PERMISSIONS + 1] {
+        let result = process(&mut ctx, &[grant_delegation(&owner, &collaborator, permissions)], &[&authority]).await;
+        assert_eq!(custom_error(result), u32::from(TodoError::InvalidPermissions));
+    }
+    let result = process(&mut ctx, &[grant_delegation(&owner, &owner, Delegation::DELETE)], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidDelegate));
+
+    // Only the owner can change or withdraw their delegation
+    process(&mut ctx, &[grant_delegation(&owner, &collaborator, Delegation::DELETE)], &[&authority])
+        .await
+        .unwrap();
+    let intruder = Keypair::new();
+    let mut revoke = revoke_delegation(&owner, &collaborator);
+    revoke.accounts[1].pubkey = intruder.pubkey();
+    let result = process(&mut ctx, &[revoke], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn test_transfer_todo_moves_it_to_the_recipient() {
+    let (sender, receiver) = (Keypair::new(), Keypair::new());
+    let (owner, recipient) = (sender.pubkey(), receiver.pubkey());
+    let mut program_test = program_test();
+    fund(&mut program_test, &sender);
+    fund(&mut program_test, &receiver);
+    let mut ctx = program_test.start_with_context().await;
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&owner),
+            create_todo(&owner, 1, "Hand me off", 1625097600),
+            set_priority(todo_address(&owner, 1), &owner, 2),
+            set_tags(todo_address(&owner, 1), &owner, &["ops"]),
+            set_status(todo_address(&owner, 1), &owner, TodoStatus::InProgress),
+        ],
+        &[&sender],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut ctx,
+        &[initialize_user(&recipient), create_todo(&recipient, 1, "Mine already", 0)],
+        &[&receiver],
+    )
+    .await
+    .unwrap();
+    let todo_account = todo_address(&owner, 1);
+    let original: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+
+    process(&mut ctx, &[offer_transfer(todo_account, &owner, &recipient)], &[&sender])
+        .await
+        .unwrap();
+    let offer: TransferOffer = fetch(&mut ctx, transfer_offer_address(&todo_account)).await.unwrap();
+    assert_eq!((offer.todo, offer.owner, offer.recipient), (todo_account, owner, recipient));
+    assert_eq!((offer.todo_id, offer.todo_created_at), (1, original.created_at));
+
+    // Only the recipient can accept
+    let intruder = Keypair::new();
+    let mut steal = transfer_todo(todo_account, &owner, &recipient, 2);
+    steal.accounts[4].pubkey = profile_address(&intruder.pubkey());
+    steal.accounts[5].pubkey = todo_address(&intruder.pubkey(), 1);
+    steal.accounts[6].pubkey = intruder.pubkey();
+    let payer = ctx.payer.pubkey();
+    let result = process(
+        &mut ctx,
+        &[
+            system_instruction::transfer(&payer, &intruder.pubkey(), LAMPORTS_PER_SOL),
+            initialize_user(&intruder.pubkey()),
+            steal,
+        ],
+        &[&intruder],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::NotTransferRecipient));
+
+    set_time(&mut ctx, 3, 1_700_000_100).await;
+    let rent = ctx.banks_client.get_balance(todo_account).await.unwrap()
+        + ctx.banks_client.get_balance(transfer_offer_address(&todo_account)).await.unwrap();
+    let owner_balance = ctx.banks_client.get_balance(owner).await.unwrap();
+    process(&mut ctx, &[transfer_todo(todo_account, &owner, &recipient, 2)], &[&receiver])
+        .await
+        .unwrap();
+
+    // The todo keeps its history under the recipient's next id
+    let moved: TodoItem = fetch(&mut ctx, todo_address(&recipient, 2)).await.unwrap();
+    assert_eq!(moved.id, 2);
+    assert_eq!((moved.owner, moved.authority), (recipient, recipient));
+    assert_eq!(moved.description, original.description);
+    assert_eq!(moved.status, TodoStatus::InProgress);
+    assert_eq!((moved.priority, moved.tags.clone()), (2, vec!["ops".to_string()]));
+    assert_eq!((moved.due_date, moved.created_at), (original.due_date, original.created_at));
+    assert_eq!(moved.updated_at, 1_700_000_100);
+
+    // The old account and the offer are closed, refunding the previous owner
+    assert!(ctx.banks_client.get_account(todo_account).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(transfer_offer_address(&todo_account)).await.unwrap().is_none());
+    assert_eq!(ctx.banks_client.get_balance(owner).await.unwrap(), owner_balance + rent);
+    let owner_profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!((owner_profile.todo_count, owner_profile.last_todo_id), (0, 1));
+    let recipient_profile: UserProfile = fetch(&mut ctx, profile_address(&recipient)).await.unwrap();
+    assert_eq!((recipient_profile.todo_count, recipient_profile.last_todo_id), (2, 2));
+}
+
+#[tokio::test]
+async fn test_transfer_offers_are_checked_and_can_be_cancelled() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let receiver = Keypair::new();
+    let recipient = receiver.pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todo_account = todo_address(&owner, 1);
+
+    let result = process(&mut ctx, &[offer_transfer(todo_account, &owner, &owner)], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::InvalidRecipient));
+
+    // Only the owner can offer their todo
+    let intruder = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let result = process(
+        &mut ctx,
+        &[
+            system_instruction::transfer(&payer, &intruder.pubkey(), LAMPORTS_PER_SOL),
+            offer_transfer(todo_account, &intruder.pubkey(), &recipient),
+        ],
+        &[&intruder],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+
+    // The owner withdraws an offer, and the recipient can decline one;
+    // anyone else is refused
+    process(&mut ctx, &[offer_transfer(todo_account, &owner, &recipient)], &[&authority])
+        .await
+        .unwrap();
+    let result = process(&mut ctx, &[cancel_transfer(todo_account, &owner, &intruder.pubkey())], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::UnauthorizedAccess));
+    process(&mut ctx, &[cancel_transfer(todo_account, &owner, &owner)], &[&authority])
+        .await
+        .unwrap();
+    assert!(ctx.banks_client.get_account(transfer_offer_address(&todo_account)).await.unwrap().is_none());
+
+    process(&mut ctx, &[offer_transfer(todo_account, &owner, &recipient)], &[&authority])
+        .await
+        .unwrap();
+    process(&mut ctx, &[cancel_transfer(todo_account, &owner, &recipient)], &[&receiver])
+        .await
+        .unwrap();
+
+    // With no pending offer there is nothing to accept
+    let result = process(
+        &mut ctx,
+        &[
+            system_instruction::transfer(&payer, &recipient, LAMPORTS_PER_SOL),
+            initialize_user(&recipient),
+            transfer_todo(todo_account, &owner, &recipient, 1),
+        ],
+        &[&receiver],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::AccountNotInitialized));
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.owner, owner);
+}
+
+#[tokio::test]
+async fn test_deleting_a_todo_closes_its_offer() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let recipient = Keypair::new().pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+    let (first, second) = (todo_address(&owner, 1), todo_address(&owner, 2));
+    process(
+        &mut ctx,
+        &[
+            create_todo(&owner, 2, "Second", 0),
+            offer_transfer(first, &owner, &recipient),
+            offer_transfer(second, &owner, &recipient),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    // Only the deleted todo's own offer can be closed with it
+    let mut wrong_offer = delete_todo(first, &owner);
+    wrong_offer.accounts.push(AccountMeta::new(transfer_offer_address(&second), false));
+    let result = process(&mut ctx, &[wrong_offer], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+
+    let rent = ctx.banks_client.get_balance(first).await.unwrap()
+        + ctx.banks_client.get_balance(transfer_offer_address(&first)).await.unwrap();
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+    process(&mut ctx, &[with_offer(delete_todo(first, &owner), &first)], &[&authority])
+        .await
+        .unwrap();
+
+    assert!(ctx.banks_client.get_account(first).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(transfer_offer_address(&first)).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(transfer_offer_address(&second)).await.unwrap().is_some());
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert_eq!(balance_after, balance_before + rent);
+}
+
+#[tokio::test]
+async fn test_stale_offers_cannot_move_a_recreated_todo() {
+    let (sender, receiver) = (Keypair::new(), Keypair::new());
+    let (owner, recipient) = (sender.pubkey(), receiver.pubkey());
+    let mut program_test = program_test();
+    fund(&mut program_test, &sender);
+    fund(&mut program_test, &receiver);
+    let mut ctx = program_test.start_with_context().await;
+    set_time(&mut ctx, 2, 1_700_000_000).await;
+    let todo_account = todo_address(&owner, 1);
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&owner),
+            create_todo(&owner, 1, "Offered", 0),
+            offer_transfer(todo_account, &owner, &recipient),
+        ],
+        &[&sender],
+    )
+    .await
+    .unwrap();
+    process(&mut ctx, &[initialize_user(&recipient)], &[&receiver])
+        .await
+        .unwrap();
+
+    // Closing the profile without the offer leaves it behind, and starting
+    // over puts a new todo at the same address
+    process(&mut ctx, &[close_user(&owner, &[todo_account])], &[&sender])
+        .await
+        .unwrap();
+    set_time(&mut ctx, 3, 1_700_000_100).await;
+    process(
+        &mut ctx,
+        &[initialize_user(&owner), create_todo(&owner, 1, "Not offered", 0)],
+        &[&sender],
+    )
+    .await
+    .unwrap();
+
+    let result = process(&mut ctx, &[transfer_todo(todo_account, &owner, &recipient, 1)], &[&receiver]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::StaleTransferOffer));
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!((todo.owner, todo.description.as_str()), (owner, "Not offered"));
+
+    // Passing the offer to close_user closes it with the todos
+    let rent = ctx.banks_client.get_balance(transfer_offer_address(&todo_account)).await.unwrap();
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+    process(&mut ctx, &[with_offer(close_user(&owner, &[todo_account]), &todo_account)], &[&sender])
+        .await
+        .unwrap();
+    assert!(ctx.banks_client.get_account(transfer_offer_address(&todo_account)).await.unwrap().is_none());
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert!(balance_after > balance_before + rent);
+}
+
+#[tokio::test]
+async fn test_delete_completed_todos() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todos: Vec<Pubkey> = (1..=3).map(|id| todo_address(&owner, id)).collect();
+    process(
+        &mut ctx,
+        &[
+            create_todo(&owner, 2, "Still open", 0),
+            create_todo(&owner, 3, "Third", 0),
+            update_todo_status(todos[0], &owner, true),
+            update_todo_status(todos[2], &owner, true),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    // One open todo fails the whole call
+    let result = process(&mut ctx, &[delete_completed_todos(&owner, &todos)], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::TodoNotCompleted));
+    assert!(ctx.banks_client.get_account(todos[0]).await.unwrap().is_some());
+
+    let mut rent = 0;
+    for address in [todos[0], todos[2]] {
+        rent += ctx.banks_client.get_balance(address).await.unwrap();
+    }
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+
+    process(
+        &mut ctx,
+        &[delete_completed_todos(&owner, &[todos[0], todos[2]])],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    assert!(ctx.banks_client.get_account(todos[0]).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(todos[1]).await.unwrap().is_some());
+    assert!(ctx.banks_client.get_account(todos[2]).await.unwrap().is_none());
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert_eq!(balance_after, balance_before + rent);
+
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!(profile.todo_count, 1);
+    assert_eq!(profile.last_todo_id, 3);
+}
+
+#[tokio::test]
+async fn test_delete_todos_closes_open_todos_and_their_offers() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let recipient = Keypair::new().pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+    let todos: Vec<Pubkey> = (1..=3).map(|id| todo_address(&owner, id)).collect();
+    process(
+        &mut ctx,
+        &[
+            create_todo(&owner, 2, "Second", 0),
+            create_todo(&owner, 3, "Third", 0),
+            update_todo_status(todos[0], &owner, true),
+            offer_transfer(todos[1], &owner, &recipient),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    let mut rent = 0;
+    for address in [todos[0], todos[1], transfer_offer_address(&todos[1])] {
+        rent += ctx.banks_client.get_balance(address).await.unwrap();
+    }
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+
+    // Open or completed, the todos go, and so does the offer
+    process(
+        &mut ctx,
+        &[with_offer(delete_todos(&owner, &todos[..2]), &todos[1])],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    for address in [todos[0], todos[1], transfer_offer_address(&todos[1])] {
+        assert!(ctx.banks_client.get_account(address).await.unwrap().is_none());
+    }
+    assert!(ctx.banks_client.get_account(todos[2]).await.unwrap().is_some());
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert_eq!(balance_after, balance_before + rent);
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!((profile.todo_count, profile.last_todo_id), (1, 3));
+
+    // The rest can then be closed with the profile
+    process(&mut ctx, &[close_user(&owner, &todos[2..])], &[&authority])
+        .await
+        .unwrap();
+    assert!(ctx.banks_client.get_account(profile_address(&owner)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_close_user_requires_every_todo() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+    let mut ctx = setup_with_todo(&authority).await;
+    process(&mut ctx, &[create_todo(&owner, 2, "Second", 0)], &[&authority])
+        .await
+        .unwrap();
+    let (first, second) = (todo_address(&owner, 1), todo_address(&owner, 2));
+
+    // Todos left open keep the profile alive
+    for todos in [vec![], vec![first]] {
+        let result = process(&mut ctx, &[close_user(&owner, &todos)], &[&authority]).await;
+        assert_eq!(custom_error(result), u32::from(TodoError::ProfileHasTodos));
+    }
+
+    // Remaining todos must be writable
+    let mut read_only = close_user(&owner, &[first, second]);
+    read_only.accounts[3].is_writable = false;
+    let result = process(&mut ctx, &[read_only], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintMut));
+
+    let mut rent = 0;
+    for address in [profile_address(&owner), first, second] {
+        rent += ctx.banks_client.get_balance(address).await.unwrap();
+    }
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+
+    process(&mut ctx, &[close_user(&owner, &[first, second])], &[&authority])
+        .await
+        .unwrap();
+
+    // Every account is gone and all of the rent is back with the authority
+    for address in [profile_address(&owner), first, second] {
+        assert!(ctx.banks_client.get_account(address).await.unwrap().is_none());
+    }
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert_eq!(balance_after, balance_before + rent);
+
+    // The wallet can start over with a fresh profile
+    process(&mut ctx, &[initialize_user(&owner)], &[&authority])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_close_user_rejects_foreign_todos() {
+    let authority = Keypair::new();
+    let other = Keypair::new();
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    fund(&mut program_test, &other);
+    let mut ctx = program_test.start_with_context().await;
+
+    process(&mut ctx, &[initialize_user(&authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&other.pubkey()),
+            create_todo(&other.pubkey(), 1, "Not yours", 0),
+        ],
+        &[&other],
+    )
+    .await
+    .unwrap();
+
+    // Another wallet's todo cannot be closed to collect its rent
+    let theirs = todo_address(&other.pubkey(), 1);
+    let result = process(&mut ctx, &[close_user(&authority.pubkey(), &[theirs])], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+    assert!(ctx.banks_client.get_account(theirs).await.unwrap().is_some());
+
+    // Nor can a profile be closed by anyone but its authority
+    let mut close_theirs = close_user(&authority.pubkey(), &[]);
+    close_theirs.accounts[0].pubkey = profile_address(&other.pubkey());
+    let result = process(&mut ctx, &[close_theirs], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+    assert!(ctx.banks_client.get_account(profile_address(&other.pubkey())).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_close_user_revokes_delegations() {
+    let (authority, other, collaborator) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let (owner, delegate) = (authority.pubkey(), collaborator.pubkey());
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    fund(&mut program_test, &other);
+    fund(&mut program_test, &collaborator);
+    let mut ctx = program_test.start_with_context().await;
+    let todo_account = todo_address(&owner, 1);
+    process(
+        &mut ctx,
+        &[
+            initialize_user(&owner),
+            create_todo(&owner, 1, "Shared", 0),
+            grant_delegation(&owner, &delegate, Delegation::ALL_PERMISSIONS),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    process(&mut ctx, &[grant_delegation(&other.pubkey(), &delegate, Delegation::SET_STATUS)], &[&other])
+        .await
+        .unwrap();
+
+    // Only delegations the authority granted can be closed
+    let theirs = delegation_address(&other.pubkey(), &delegate);
+    let mut close_theirs = close_user(&owner, &[todo_account]);
+    close_theirs.accounts.push(AccountMeta::new(theirs, false));
+    let result = process(&mut ctx, &[close_theirs], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+
+    let mine = delegation_address(&owner, &delegate);
+    let mut rent = 0;
+    for address in [profile_address(&owner), todo_account, mine] {
+        rent += ctx.banks_client.get_balance(address).await.unwrap();
+    }
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+    let mut close = close_user(&owner, &[todo_account]);
+    close.accounts.push(AccountMeta::new(mine, false));
+    process(&mut ctx, &[close], &[&authority]).await.unwrap();
+    assert!(ctx.banks_client.get_account(mine).await.unwrap().is_none());
+    assert!(ctx.banks_client.get_account(theirs).await.unwrap().is_some());
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert_eq!(balance_after, balance_before + rent);
+
+    // Starting over does not bring the collaborator's access back
+    process(
+        &mut ctx,
+        &[initialize_user(&owner), create_todo(&owner, 1, "Private now", 0)],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let update = with_delegation(set_status(todo_account, &delegate, TodoStatus::InProgress), &owner, &delegate);
+    let result = process(&mut ctx, &[update], &[&collaborator]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::AccountNotInitialized));
+}
+
+// An account of the todo program holding `value` behind `discriminator`,
+// allocated at `space` bytes
+fn stored_account<T: AnchorSerialize>(discriminator: [u8; 8], value: &T, space: usize) -> Account {
+    let mut data = discriminator.to_vec();
+    value.serialize(&mut data).unwrap();
+    data.resize(space, 0);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: solana_todo::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_migrate_todo_grows_legacy_account() {
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    let legacy = TodoItemV0 {
+        id: 1,
+        description: "x".repeat(270),
+        completed: true,
+        due_date: 1625097600,
+        owner: authority.pubkey(),
+        authority: authority.pubkey(),
+    };
+    program_test.add_account(
+        todo_account,
+        stored_account(TodoItem::DISCRIMINATOR, &legacy, TodoItemV0::SPACE),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let result = process(&mut ctx, &[migrate_todo(todo_account, &intruder.pubkey())], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintHasOne));
+
+    let balance_before = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+    let rent_before = ctx.banks_client.get_balance(todo_account).await.unwrap();
+    process(&mut ctx, &[migrate_todo(todo_account, &authority.pubkey())], &[&authority])
+        .await
+        .unwrap();
+
+    // The authority pays the extra rent for the larger account
+    let space = TodoItem::space(&legacy.description);
+    let account = ctx.banks_client.get_account(todo_account).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), space);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(space));
+    let balance_after = ctx.banks_client.get_balance(authority.pubkey()).await.unwrap();
+    assert_eq!(balance_before - balance_after, account.lamports - rent_before);
+
+    let todo: TodoItem = fetch(&mut ctx, todo_account).await.unwrap();
+    assert_eq!(todo.description, "x".repeat(270));
+    assert!(todo.completed());
+    assert_eq!(todo.owner, authority.pubkey());
+    assert_eq!((todo.created_at, todo.updated_at, todo.completed_at), (0, 0, None));
+
+    // The migrated account grows to hold the longest description
+    process(
+        &mut ctx,
+        &[update_description(todo_account, &authority.pubkey(), &"y".repeat(TodoItem::MAX_DESCRIPTION_CHARS))],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    let result = process(&mut ctx, &[migrate_todo(todo_account, &authority.pubkey())], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::AlreadyMigrated));
+}
+
+#[tokio::test]
+async fn test_migrate_todo_upgrades_every_unversioned_layout() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+
+    // Todos created after timestamps were recorded, before and after
+    // priorities and tags were added
+    let v1 = TodoItemV1 {
+        id: 1,
+        description: "Untagged".to_string(),
+        status: TodoStatus::InProgress,
+        due_date: 1625097600,
+        owner,
+        authority: owner,
+        created_at: 1_690_000_000,
+        updated_at: 1_690_000_100,
+        completed_at: None,
+    };
+    let v2 = TodoItemV2 {
+        id: 2,
+        description: "Tagged".to_string(),
+        status: TodoStatus::Done,
+        due_date: 1625097600,
+        owner,
+        authority: owner,
+        created_at: 1_690_000_000,
+        updated_at: 1_690_000_200,
+        completed_at: Some(1_690_000_200),
+        priority: 2,
+        tags: vec!["old".to_string()],
+    };
+
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    program_test.add_account(
+        todo_address(&owner, 1),
+        stored_account(TodoItem::DISCRIMINATOR, &v1, TodoItemV1::SPACE),
+    );
+    program_test.add_account(
+        todo_address(&owner, 2),
+        stored_account(TodoItem::DISCRIMINATOR, &v2, TodoItemV2::SPACE),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    // Unmigrated accounts cannot be used by the other instructions
+    let result = process(&mut ctx, &[set_tags(todo_address(&owner, 2), &owner, &["new"])], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::AccountNeedsMigration));
+    let result = process(
+        &mut ctx,
+        &[initialize_user(&owner), delete_todo(todo_address(&owner, 1), &owner)],
+        &[&authority],
+    )
+    .await;
+    assert_eq!(custom_error(result), u32::from(TodoError::AccountNeedsMigration));
+
+    process(
+        &mut ctx,
+        &[
+            migrate_todo(todo_address(&owner, 1), &owner),
+            migrate_todo(todo_address(&owner, 2), &owner),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    // Both shrink to fit their short descriptions
+    for (id, description) in [(1, &v1.description), (2, &v2.description)] {
+        let account = ctx.banks_client.get_account(todo_address(&owner, id)).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), TodoItem::space(description));
+        assert_eq!(TodoItem::stored_version(&account.data), Some(TodoItem::VERSION));
+    }
+
+    // Recorded times survive; fields the layout lacked start out empty
+    let first: TodoItem = fetch(&mut ctx, todo_address(&owner, 1)).await.unwrap();
+    assert_eq!(first.version, TodoItem::VERSION);
+    assert_eq!(first.description, "Untagged");
+    assert_eq!(first.status, TodoStatus::InProgress);
+    assert_eq!((first.created_at, first.updated_at), (1_690_000_000, 1_690_000_100));
+    assert_eq!(first.priority, 0);
+    assert!(first.tags.is_empty());
+
+    let second: TodoItem = fetch(&mut ctx, todo_address(&owner, 2)).await.unwrap();
+    assert_eq!(second.completed_at, Some(1_690_000_200));
+    assert_eq!(second.priority, 2);
+    assert_eq!(second.tags, vec!["old"]);
+
+    // A new slot, so this is not the same transaction as the rejected one
+    ctx.warp_to_slot(2).unwrap();
+    process(&mut ctx, &[set_tags(todo_address(&owner, 2), &owner, &["new"])], &[&authority])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_todo_moves_the_owner_to_a_fixed_offset() {
+    let authority = Keypair::new();
+    let owner = authority.pubkey();
+
+    // Version 3 todos kept the owner after the description. The second one
+    // fills an account the size of an unversioned layout exactly.
+    let v3 = |id: u64, description: String| TodoItemV3 {
+        version: 3,
+        id,
+        description,
+        status: TodoStatus::InProgress,
+        due_date: 1625097600,
+        owner,
+        authority: owner,
+        created_at: 1_690_000_000,
+        updated_at: 1_690_000_100,
+        completed_at: None,
+        priority: 1,
+        tags: vec!["v3".to_string()],
+    };
+    let short = v3(1, "Short".to_string());
+    let sized = v3(2, "x".repeat(TodoItemV2::SPACE - TodoItem::BASE_SPACE));
+
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    for todo in [&short, &sized] {
+        program_test.add_account(
+            todo_address(&owner, todo.id),
+            stored_account(TodoItem::DISCRIMINATOR, todo, TodoItem::space(&todo.description)),
+        );
+    }
+    let mut ctx = program_test.start_with_context().await;
+
+    for todo in [&short, &sized] {
+        let account = ctx.banks_client.get_account(todo_address(&owner, todo.id)).await.unwrap().unwrap();
+        assert_eq!(TodoItem::stored_version(&account.data), Some(3));
+    }
+    let result = process(&mut ctx, &[set_tags(todo_address(&owner, 1), &owner, &["new"])], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::AccountNeedsMigration));
+
+    process(
+        &mut ctx,
+        &[
+            migrate_todo(todo_address(&owner, 1), &owner),
+            migrate_todo(todo_address(&owner, 2), &owner),
+        ],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    for todo in [&short, &sized] {
+        let account = ctx.banks_client.get_account(todo_address(&owner, todo.id)).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), TodoItem::space(&todo.description));
+        assert_eq!(
+            &account.data[TodoItem::OWNER_OFFSET..TodoItem::OWNER_OFFSET + 32],
+            owner.as_ref()
+        );
+
+        let migrated: TodoItem = fetch(&mut ctx, todo_address(&owner, todo.id)).await.unwrap();
+        assert_eq!(migrated.version, TodoItem::VERSION);
+        assert_eq!(migrated.description, todo.description);
+        assert_eq!((migrated.priority, migrated.tags.clone()), (1, vec!["v3".to_string()]));
+    }
+}
+
+#[tokio::test]
+async fn test_migrate_todo_rejects_unknown_versions() {
+    let authority = Keypair::new();
+    let todo_account = todo_address(&authority.pubkey(), 1);
+
+    // A layout version newer than this program knows about
+    let mut data = TodoItem::DISCRIMINATOR.to_vec();
+    data.push(TodoItem::VERSION + 1);
+    data.resize(TodoItem::space(&"x".repeat(300)), 0);
+
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    program_test.add_account(
+        todo_account,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: solana_todo::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let result = process(&mut ctx, &[migrate_todo(todo_account, &authority.pubkey())], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::UnsupportedVersion));
+}
+
+#[tokio::test]
+async fn test_migrate_profile_upgrades_unversioned_profile() {
+    let authority = Keypair::new();
+    let intruder = Keypair::new();
+    let owner = authority.pubkey();
+
+    // A profile created before versioning, with one todo in the current layout
+    let legacy = UserProfileV0 {
+        authority: owner,
+        todo_count: 1,
+        last_todo_id: 1,
+    };
+    let mut program_test = program_test();
+    fund(&mut program_test, &authority);
+    fund(&mut program_test, &intruder);
+    program_test.add_account(
+        profile_address(&owner),
+        stored_account(UserProfile::DISCRIMINATOR, &legacy, UserProfileV0::SPACE),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let result = process(&mut ctx, &[create_todo(&owner, 2, "Blocked", 0)], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::AccountDidNotDeserialize));
+
+    // The profile address is derived from the signer, so nobody else can migrate it
+    let mut foreign = migrate_profile(&intruder.pubkey());
+    foreign.accounts[0].pubkey = profile_address(&owner);
+    let result = process(&mut ctx, &[foreign], &[&intruder]).await;
+    assert_eq!(custom_error(result), u32::from(AnchorErrorCode::ConstraintSeeds));
+
+    let balance_before = ctx.banks_client.get_balance(owner).await.unwrap();
+    let rent_before = ctx.banks_client.get_balance(profile_address(&owner)).await.unwrap();
+    process(&mut ctx, &[migrate_profile(&owner)], &[&authority])
+        .await
+        .unwrap();
+
+    let account = ctx.banks_client.get_account(profile_address(&owner)).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), UserProfile::SPACE);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(UserProfile::SPACE));
+    let balance_after = ctx.banks_client.get_balance(owner).await.unwrap();
+    assert_eq!(balance_before - balance_after, account.lamports - rent_before);
+
+    let profile: UserProfile = fetch(&mut ctx, profile_address(&owner)).await.unwrap();
+    assert_eq!(profile.version, UserProfile::VERSION);
+    assert_eq!(profile.authority, owner);
+    assert_eq!((profile.todo_count, profile.last_todo_id), (1, 1));
+
+    // The counter carries on where the old profile left off
+    process(&mut ctx, &[create_todo(&owner, 2, "Unblocked", 0)], &[&authority])
+        .await
+        .unwrap();
+
+    let result = process(&mut ctx, &[migrate_profile(&owner)], &[&authority]).await;
+    assert_eq!(custom_error(result), u32::from(TodoError::AlreadyMigrated));
+}
@@ -161,6 +161,8 @@ describe("solana_todo", () => {
           userProfile: userProfilePda,
           todoAccount: firstTodoPda,
           authority: userWallet.publicKey,
+          owner: userWallet.publicKey,
+          delegation: null,
         })
         .rpc();
 